@@ -1,29 +1,992 @@
 use anyhow::{anyhow, Context};
 use colored::*;
-use prettytable::{cell, format, row, Table};
+use lazy_static::lazy_static;
+use prettytable::{cell, format, row, Cell, Table};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, Ordering};
 use structopt::StructOpt;
-use uom::si::f64::{Length, Time, Velocity};
-use uom::si::length::{foot, kilometer, meter, mile, yard};
+use uom::si::f64::{Length, Mass, Ratio, Time, Velocity};
+use uom::si::length::{centimeter, foot, inch, kilometer, meter, mile, yard};
+use uom::si::mass::{kilogram, pound};
 use uom::si::ratio::{percent, ratio};
-use uom::si::time::{hour, minute, second};
-use uom::si::velocity::{kilometer_per_hour, mile_per_hour};
+use uom::si::time::{day, hour, minute, second};
+use uom::si::velocity::{kilometer_per_hour, meter_per_second, mile_per_hour};
 use uom::si::Unit;
 
 #[derive(StructOpt, Debug)]
 #[structopt(
     name = "Today I Ran",
-    about = "This tool provides you with basic information derived from the distance you ran and the time you needed. This currently contains your average velocity, estimated times for other distances and comparisons with other performances."
+    about = "This tool provides you with basic information derived from the distance you ran and the time you needed. This currently contains your average velocity, estimated times for other distances and comparisons with other performances. Without a subcommand, `run` is assumed."
 )]
 struct CommandLineOptions {
-    #[structopt(help = "the distance you ran today")]
-    distance: String,
-    #[structopt(help = "the time you needed")]
-    time: String,
+    #[structopt(
+        long = "no-color",
+        global = true,
+        help = "do not use colors in the output"
+    )]
+    no_color: bool,
+    #[structopt(
+        long = "format",
+        global = true,
+        default_value = "text",
+        possible_values = &["text", "markdown"],
+        help = "whether to print the report as plain text or as Markdown, e.g. for a training journal"
+    )]
+    format: ReportFormat,
+    #[structopt(
+        long = "print-schema",
+        help = "print the JSON schema of the comparison written by --compare-out"
+    )]
+    print_schema: bool,
+    #[structopt(
+        long = "list-units",
+        help = "print all units you can give distances, times and velocities in"
+    )]
+    list_units: bool,
+    #[structopt(
+        long = "json-input",
+        help = "read a JSON array of runs with distance and time from stdin and print the results as JSON"
+    )]
+    json_input: bool,
+    #[structopt(
+        long = "min-pace",
+        requires = "json-input",
+        help = "only output the runs at this pace per kilometer or slower, e.g. 4:30 or 7:15/mi"
+    )]
+    min_pace: Option<String>,
+    #[structopt(
+        long = "max-pace",
+        requires = "json-input",
+        help = "only output the runs at this pace per kilometer or faster, e.g. 5:00 or 8:00/mi"
+    )]
+    max_pace: Option<String>,
+    #[structopt(
+        long = "sanity-json",
+        requires = "json-input",
+        help = "add the warnings about likely mistakes in a run to its result instead of printing them"
+    )]
+    sanity_json: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    #[structopt(about = "analyse the run you did today (default)")]
+    Run(RunOptions),
+    #[structopt(about = "estimate the time you would need for other distances")]
+    Predict(PredictOptions),
+    #[structopt(about = "convert a distance into other units of length")]
+    Convert(ConvertOptions),
+    #[structopt(about = "summarise several runs")]
+    Summary(SummaryOptions),
+    #[structopt(about = "calculate the velocity you need to run a distance in a target time")]
+    Target(TargetOptions),
+    #[structopt(about = "calculate the totals of an interval session")]
+    Intervals(IntervalOptions),
+    #[structopt(about = "print only the VDOT of a race result")]
+    Vdot(VdotOptions),
+    #[structopt(about = "create a pace band with the times to reach each kilometer or mile")]
+    Band(BandOptions),
+    #[structopt(about = "time your laps by pressing Enter, like with a stopwatch")]
+    Live(LiveOptions),
+    #[structopt(about = "plan the weekly distance to build up to a goal race")]
+    Plan(PlanOptions),
+    #[structopt(about = "convert a pace into other units of pace and velocity")]
+    PaceTable(PaceTableOptions),
+    #[structopt(about = "calculate the velocity and the times for other distances at a pace")]
+    Pace(PaceOptions),
+    #[structopt(about = "project when you would reach a target time if you keep improving")]
+    Project(ProjectOptions),
+}
+
+/// The names of all subcommands, used to detect when the default subcommand is meant.
+const SUBCOMMANDS: [&str; 13] = [
+    "run",
+    "predict",
+    "convert",
+    "summary",
+    "target",
+    "intervals",
+    "vdot",
+    "band",
+    "live",
+    "plan",
+    "pace-table",
+    "pace",
+    "project",
+];
+
+#[derive(StructOpt, Debug)]
+struct RunArguments {
+    #[structopt(
+        required_unless_one = &["fit", "gpx"],
+        help = "the distance you ran today, or the time you needed if you give --pace"
+    )]
+    distance: Option<String>,
+    #[structopt(
+        required_unless_one = &["pace", "fit", "gpx"],
+        help = "the time you needed"
+    )]
+    time: Option<String>,
+    #[structopt(
+        long = "pace",
+        conflicts_with = "time",
+        help = "the constant pace you ran, per kilometer (or per mile with --miles), to derive the missing distance or time"
+    )]
+    pace: Option<String>,
+    #[structopt(
+        long = "fit",
+        conflicts_with_all = &["distance", "time", "pace"],
+        help = "read distance and elapsed time from this FIT activity file instead"
+    )]
+    fit: Option<String>,
+    #[structopt(
+        long = "gpx",
+        conflicts_with_all = &["distance", "time", "pace", "fit"],
+        help = "read distance and elapsed time from the track in this GPX file instead"
+    )]
+    gpx: Option<String>,
+    #[structopt(
+        long = "smart-units",
+        conflicts_with = "use-miles",
+        help = "read a distance without unit as kilometers if it is below 100 and as meters otherwise"
+    )]
+    smart_units: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct RunOptions {
+    #[structopt(flatten)]
+    run: RunArguments,
     #[structopt(short = "v", long = "verbose", help = "show additional information")]
     verbose: bool,
     #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
     use_miles: bool,
+    #[structopt(
+        long = "units-in",
+        possible_values = &["metric", "imperial"],
+        help = "the units to read paces in when they have none, by default the output units"
+    )]
+    units_in: Option<UnitSystem>,
+    #[structopt(
+        long = "units-out",
+        possible_values = &["metric", "imperial"],
+        conflicts_with = "use-miles",
+        help = "the units to show results in, where imperial is the same as --miles"
+    )]
+    units_out: Option<UnitSystem>,
+    #[structopt(
+        long = "pace-per",
+        help = "the distance to give your pace for, like km, mi, 400m or 1000m, by default per kilometer (or per mile with --miles)"
+    )]
+    pace_per: Option<String>,
+    #[structopt(
+        long = "pace-format",
+        default_value = "mmss",
+        possible_values = &["mmss", "tenths", "decimal"],
+        help = "whether to show pace in minutes and seconds, in minutes and seconds with tenths like 5:12.3, or in decimal minutes, like 5.5 min"
+    )]
+    pace_format: PaceFormat,
+    #[structopt(
+        long = "mirror",
+        help = "show distance, velocity and pace in both metric and imperial units"
+    )]
+    mirror: bool,
+    #[structopt(
+        long = "rate",
+        help = "award your run a bronze, silver, gold or platinum tier based on its VDOT"
+    )]
+    rate: bool,
+    #[structopt(
+        long = "compare-percent-of-wr",
+        help = "compare your average velocity with the world record closest to your distance"
+    )]
+    compare_percent_of_wr: bool,
+    #[structopt(
+        long = "elevation",
+        allow_hyphen_values = true,
+        help = "the net elevation change of your run, negative for a net descent"
+    )]
+    elevation: Option<String>,
+    #[structopt(
+        long = "vertical",
+        requires = "elevation",
+        help = "also show the climbing rate for the elevation gain, like for a vertical kilometer"
+    )]
+    vertical: bool,
+    #[structopt(long = "show-formula", help = "show how the values are calculated")]
+    show_formula: bool,
+    #[structopt(
+        long = "explain",
+        help = "first show how your distance and time were understood"
+    )]
+    explain: bool,
+    #[structopt(
+        long = "warmup",
+        help = "the part of your run that was warmup, given as <distance>:<time>"
+    )]
+    warmup: Option<String>,
+    #[structopt(
+        long = "cooldown",
+        help = "the part of your run that was cooldown, given as <distance>:<time>"
+    )]
+    cooldown: Option<String>,
+    #[structopt(long = "weight", help = "your body weight, e.g. 70kg or 154lb")]
+    weight: Option<String>,
+    #[structopt(long = "height", help = "your body height, e.g. 180cm or 71in")]
+    height: Option<String>,
+    #[structopt(
+        long = "cross-train",
+        help = "estimate an equivalent effort on an indoor rower or a bike erg in verbose mode"
+    )]
+    cross_train: bool,
+    #[structopt(
+        long = "calories-model",
+        default_value = "simple",
+        possible_values = &["simple", "acsm"],
+        help = "how to estimate the energy you burned when your weight is given"
+    )]
+    calories_model: CaloriesModel,
+    #[structopt(
+        long = "compare-within",
+        help = "only compare with performances whose velocity is within this many percent of yours"
+    )]
+    compare_within: Option<f64>,
+    #[structopt(
+        long = "compare-closest",
+        help = "only compare with this many performances closest to yours"
+    )]
+    compare_closest: Option<usize>,
+    #[structopt(
+        long = "compare-to-pace",
+        help = "compare your pace with this one, given per kilometer (or per mile with --miles)"
+    )]
+    compare_to_pace: Option<String>,
+    #[structopt(
+        long = "compare-velocity",
+        help = "also compare with this velocity, e.g. 18km/h or 11mph"
+    )]
+    compare_velocity: Option<String>,
+    #[structopt(
+        long = "compare-notes",
+        help = "add a note on the conditions to the other performances where they matter"
+    )]
+    compare_notes: bool,
+    #[structopt(
+        long = "fun",
+        help = "add a comparison with a randomly chosen other performance"
+    )]
+    fun: bool,
+    #[structopt(
+        long = "seed",
+        requires = "fun",
+        help = "the seed for choosing the performance of --fun, to always choose the same one"
+    )]
+    seed: Option<u64>,
+    #[structopt(
+        long = "compare-equivalent",
+        help = "also compare with the world records after mapping your run to their distances"
+    )]
+    compare_equivalent: bool,
+    #[structopt(
+        long = "compare-basis",
+        default_value = "speed",
+        possible_values = &["speed", "pace", "record-pace"],
+        help = "whether to compare with other performances by speed, by pace or by the difference to the pace sustained in them"
+    )]
+    compare_basis: CompareBasis,
+    #[structopt(
+        long = "field-size",
+        help = "estimate your finishing position in a race with this many runners"
+    )]
+    field_size: Option<usize>,
+    #[structopt(
+        long = "compact",
+        help = "print everything important on a single line, separated by pipes"
+    )]
+    compact: bool,
+    #[structopt(
+        long = "style",
+        default_value = "default",
+        possible_values = &["default", "strava"],
+        help = "how to present your run, strava prints a summary card like the activity app"
+    )]
+    style: OutputStyle,
+    #[structopt(
+        long = "share",
+        help = "print a single sentence about your run to share on social media"
+    )]
+    share: bool,
+    #[structopt(
+        long = "no-emoji",
+        requires = "share",
+        help = "leave out the emoji in the sentence printed by --share"
+    )]
+    no_emoji: bool,
+    #[structopt(flatten)]
+    goal: GoalOptions,
+    #[structopt(
+        long = "splits",
+        help = "the times of the splits of your run, to see how evenly you paced it"
+    )]
+    splits: Vec<String>,
+    #[structopt(
+        long = "best-split",
+        requires = "splits",
+        help = "list your splits with the fastest and slowest one marked"
+    )]
+    best_split: bool,
+    #[structopt(
+        long = "fade-analysis",
+        requires = "splits",
+        help = "compare the first and second half of your splits to see whether you faded"
+    )]
+    fade_analysis: bool,
+    #[structopt(
+        long = "compare-out",
+        help = "write the comparison with other performances to this file, as JSON or CSV depending on its extension"
+    )]
+    compare_out: Option<String>,
+    #[structopt(
+        long = "predict-from",
+        help = "estimate the time you should have needed from another run, e.g. \"10km 52min\""
+    )]
+    predict_from: Option<String>,
+    #[structopt(
+        long = "elapsed",
+        help = "the total elapsed time of your run including stops, if the time you give is the moving time"
+    )]
+    elapsed: Option<String>,
+    #[structopt(
+        long = "target-vdot",
+        help = "show the race times that correspond to this VDOT"
+    )]
+    target_vdot: Option<f64>,
+    #[structopt(
+        long = "sex",
+        possible_values = &["f", "m"],
+        help = "your sex, to also compare with other performances adjusted for the gender gap"
+    )]
+    sex: Option<Sex>,
+    #[structopt(
+        long = "age",
+        help = "your age in years, to also compare with other performances by age grade"
+    )]
+    age: Option<u32>,
+    #[structopt(
+        long = "no-comparisons",
+        help = "do not compare with other performances in verbose mode"
+    )]
+    no_comparisons: bool,
+    #[structopt(
+        long = "no-distance-table",
+        help = "do not show the times for other distances in verbose mode"
+    )]
+    no_distance_table: bool,
+    #[structopt(
+        long = "carbs-per-hour",
+        default_value = "60",
+        help = "the grams of carbohydrates per hour to plan with for long races"
+    )]
+    carbs_per_hour: f64,
+    #[structopt(
+        long = "model",
+        default_value = "linear",
+        possible_values = &["linear", "riegel", "cameron"],
+        help = "how to estimate the times for other distances: at the same pace, or getting slower over longer distances following Riegel's or Cameron's formula"
+    )]
+    model: PredictionModel,
+    #[structopt(
+        long = "relative",
+        help = "show the time between consecutive distances instead of the total time"
+    )]
+    relative: bool,
+    #[structopt(
+        long = "race-temp",
+        allow_hyphen_values = true,
+        help = "the expected temperature on race day in °C, to also show predictions adjusted for the heat"
+    )]
+    race_temp: Option<f64>,
+    #[structopt(
+        long = "humidity",
+        help = "the relative humidity in percent, to adjust the temperature of --race-temp or --temp for the heat index"
+    )]
+    humidity: Option<f64>,
+    #[structopt(
+        long = "standardize",
+        help = "estimate your time in standard conditions, correcting for --temp, --humidity, --altitude, --wind and --elevation"
+    )]
+    standardize: bool,
+    #[structopt(
+        long = "temp",
+        allow_hyphen_values = true,
+        requires = "standardize",
+        help = "the temperature during your run in °C"
+    )]
+    temp: Option<f64>,
+    #[structopt(
+        long = "altitude",
+        requires = "standardize",
+        help = "the altitude you ran at"
+    )]
+    altitude: Option<String>,
+    #[structopt(
+        long = "wind",
+        allow_hyphen_values = true,
+        requires = "standardize",
+        help = "the headwind during your run, negative for a tailwind, e.g. 10km/h"
+    )]
+    wind: Option<String>,
+    #[structopt(
+        long = "threshold",
+        help = "your threshold pace, per kilometer (or per mile with --miles), to derive training zones from"
+    )]
+    threshold: Option<String>,
+    #[structopt(
+        long = "heart-rate",
+        help = "your average heart rate in bpm, for the effort score in verbose mode"
+    )]
+    heart_rate: Option<u32>,
+    #[structopt(
+        long = "max-heart-rate",
+        help = "your maximum heart rate in bpm, for the effort score in verbose mode"
+    )]
+    max_heart_rate: Option<u32>,
+    #[structopt(
+        long = "strict",
+        help = "treat an implausible combination of distance and time as an error instead of a warning"
+    )]
+    strict: bool,
+    #[structopt(long = "save", help = "save this run to your history")]
+    save: bool,
+    #[structopt(
+        long = "note",
+        requires = "save",
+        help = "a note to save with this run, e.g. \"easy recovery\""
+    )]
+    note: Option<String>,
+    #[structopt(
+        long = "tag",
+        requires = "save",
+        help = "the tags to save with this run, separated by commas, e.g. long,trail"
+    )]
+    tags: Option<String>,
+    #[structopt(
+        long = "compare-to-last",
+        help = "compare your pace with the one of the latest run in your history"
+    )]
+    compare_to_last: bool,
+    #[structopt(
+        long = "goal-distance",
+        help = "the full distance of the race you are in the middle of, to treat your run as the part completed so far"
+    )]
+    goal_distance: Option<String>,
+    #[structopt(
+        long = "goal-time",
+        requires = "goal-distance",
+        help = "the time you want to finish the race given by --goal-distance in"
+    )]
+    goal_time: Option<String>,
+    #[structopt(
+        long = "progress-bar",
+        requires = "goal-distance",
+        help = "draw how much of the race given by --goal-distance you have completed"
+    )]
+    progress_bar: bool,
+    #[structopt(flatten)]
+    history: HistoryOptions,
+}
+
+#[derive(StructOpt, Debug)]
+struct HistoryOptions {
+    #[structopt(
+        long = "history",
+        help = "the file your runs are saved in, ~/.tir_history.csv by default"
+    )]
+    history: Option<String>,
+}
+
+#[derive(StructOpt, Debug)]
+struct GoalOptions {
+    #[structopt(
+        long = "goal",
+        number_of_values = 2,
+        value_names = &["time", "distance"],
+        help = "compare the predicted times with your goal, e.g. 3:30:00 marathon"
+    )]
+    goal: Vec<String>,
+    #[structopt(
+        long = "goal-days",
+        requires = "goal",
+        help = "the number of days until your goal race, to tell how much you need to improve per day"
+    )]
+    goal_days: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareBasis {
+    Speed,
+    Pace,
+    RecordPace,
+}
+
+impl std::str::FromStr for CompareBasis {
+    type Err = String;
+
+    fn from_str(basis: &str) -> Result<Self, Self::Err> {
+        match basis {
+            "speed" => Ok(CompareBasis::Speed),
+            "pace" => Ok(CompareBasis::Pace),
+            "record-pace" => Ok(CompareBasis::RecordPace),
+            _ => Err(format!("Unknown comparison basis \"{}\".", basis)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(period: &str) -> Result<Self, Self::Err> {
+        match period {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(format!("Unknown period \"{}\".", period)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PaceFormat {
+    MinutesSeconds,
+    Tenths,
+    DecimalMinutes,
+}
+
+impl std::str::FromStr for PaceFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "mmss" => Ok(PaceFormat::MinutesSeconds),
+            "tenths" => Ok(PaceFormat::Tenths),
+            "decimal" => Ok(PaceFormat::DecimalMinutes),
+            _ => Err(format!("Unknown pace format \"{}\".", format)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputStyle {
+    Default,
+    Strava,
+}
+
+impl std::str::FromStr for OutputStyle {
+    type Err = String;
+
+    fn from_str(style: &str) -> Result<Self, Self::Err> {
+        match style {
+            "default" => Ok(OutputStyle::Default),
+            "strava" => Ok(OutputStyle::Strava),
+            _ => Err(format!("Unknown style \"{}\".", style)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CaloriesModel {
+    Simple,
+    Acsm,
+}
+
+impl std::str::FromStr for CaloriesModel {
+    type Err = String;
+
+    fn from_str(model: &str) -> Result<Self, Self::Err> {
+        match model {
+            "simple" => Ok(CaloriesModel::Simple),
+            "acsm" => Ok(CaloriesModel::Acsm),
+            _ => Err(format!("Unknown calories model \"{}\".", model)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sex {
+    Female,
+    Male,
+}
+
+impl std::str::FromStr for Sex {
+    type Err = String;
+
+    fn from_str(sex: &str) -> Result<Self, Self::Err> {
+        match sex {
+            "f" => Ok(Sex::Female),
+            "m" => Ok(Sex::Male),
+            _ => Err(format!("Unknown sex \"{}\".", sex)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+    Text,
+    Markdown,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(ReportFormat::Text),
+            "markdown" => Ok(ReportFormat::Markdown),
+            _ => Err(format!("Unknown format \"{}\".", format)),
+        }
+    }
+}
+
+/// Whether the report is printed as Markdown, set once from `--format`.
+static MARKDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Prints a line of the report, followed by an empty line in Markdown so that consecutive lines
+/// stay separate paragraphs instead of merging into one.
+macro_rules! report {
+    ($($argument:tt)*) => {{
+        println!($($argument)*);
+        if MARKDOWN.load(Ordering::Relaxed) {
+            println!();
+        }
+    }};
+}
+
+/// Emphasizes a part of the report, in bold on the terminal or with asterisks in Markdown.
+trait Emphasize {
+    fn emphasized(&self) -> String;
+}
+
+impl<T: std::fmt::Display> Emphasize for T {
+    fn emphasized(&self) -> String {
+        let text = self.to_string();
+        if MARKDOWN.load(Ordering::Relaxed) {
+            // Leading line breaks separate headings from what is above, so they stay outside.
+            let content = text.trim_start_matches('\n');
+            format!("{}**{}**", &text[..text.len() - content.len()], content)
+        } else {
+            text.bold().to_string()
+        }
+    }
+}
+
+/// Prints a table, as a Markdown table with an empty header row in Markdown.
+fn print_table(table: &Table) {
+    if !MARKDOWN.load(Ordering::Relaxed) {
+        table.printstd();
+        return;
+    }
+    let columns = table.row_iter().map(|row| row.len()).max().unwrap_or(0);
+    println!();
+    println!("|{}", " |".repeat(columns));
+    println!("|{}", " --- |".repeat(columns));
+    for row in table.row_iter() {
+        let cells = row
+            .iter()
+            .map(|cell| format!(" {} |", cell.get_content().trim().replace('|', "\\|")))
+            .collect::<String>();
+        println!("|{}{}", cells, " |".repeat(columns - row.len()));
+    }
+    println!();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnitSystem {
+    Metric,
+    Imperial,
+}
+
+impl std::str::FromStr for UnitSystem {
+    type Err = String;
+
+    fn from_str(system: &str) -> Result<Self, Self::Err> {
+        match system {
+            "metric" => Ok(UnitSystem::Metric),
+            "imperial" => Ok(UnitSystem::Imperial),
+            _ => Err(format!("Unknown unit system \"{}\".", system)),
+        }
+    }
+}
+
+impl RunOptions {
+    /// Whether values given without a unit are read in miles.
+    fn input_in_miles(&self) -> bool {
+        match self.units_in {
+            Some(system) => system == UnitSystem::Imperial,
+            None => self.use_miles,
+        }
+    }
+}
+
+/// How to estimate the time for another distance from a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PredictionModel {
+    /// At the same pace.
+    Linear,
+    /// With Peter Riegel's formula.
+    Riegel,
+    /// With Dave Cameron's formula.
+    Cameron,
+}
+
+impl std::str::FromStr for PredictionModel {
+    type Err = String;
+
+    fn from_str(model: &str) -> Result<Self, Self::Err> {
+        match model {
+            "linear" => Ok(PredictionModel::Linear),
+            "riegel" => Ok(PredictionModel::Riegel),
+            "cameron" => Ok(PredictionModel::Cameron),
+            _ => Err(format!("Unknown prediction model \"{}\".", model)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+struct PredictOptions {
+    #[structopt(flatten)]
+    run: RunArguments,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(long = "show-formula", help = "show how the values are calculated")]
+    show_formula: bool,
+    #[structopt(flatten)]
+    goal: GoalOptions,
+    #[structopt(
+        long = "model",
+        default_value = "linear",
+        possible_values = &["linear", "riegel", "cameron"],
+        help = "how to estimate the times for other distances: at the same pace, or getting slower over longer distances following Riegel's or Cameron's formula"
+    )]
+    model: PredictionModel,
+    #[structopt(
+        long = "relative",
+        help = "show the time between consecutive distances instead of the total time"
+    )]
+    relative: bool,
+    #[structopt(
+        long = "race-temp",
+        allow_hyphen_values = true,
+        help = "the expected temperature on race day in °C, to also show predictions adjusted for the heat"
+    )]
+    race_temp: Option<f64>,
+    #[structopt(
+        long = "humidity",
+        requires = "race-temp",
+        help = "the expected relative humidity on race day in percent, to adjust for the heat index instead"
+    )]
+    humidity: Option<f64>,
+}
+
+#[derive(StructOpt, Debug)]
+struct ConvertOptions {
+    #[structopt(help = "the distance to convert")]
+    distance: String,
+}
+
+#[derive(StructOpt, Debug)]
+struct SummaryOptions {
+    #[structopt(help = "the runs to summarise, given as pairs of distance and time")]
+    runs: Vec<String>,
+    #[structopt(
+        long = "input-file",
+        help = "also summarise the runs in this CSV file, which needs the columns distance and time"
+    )]
+    input_file: Option<String>,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(
+        long = "profile",
+        help = "fit a trend of how your pace gets slower with the distance"
+    )]
+    profile: bool,
+    #[structopt(
+        long = "since-pr",
+        help = "tell how long ago you set your personal records, according to your history"
+    )]
+    since_pr: bool,
+    #[structopt(
+        long = "group-by",
+        possible_values = &["day", "week", "month"],
+        help = "show the totals of your history per day, week (the default) or month"
+    )]
+    group_by: Option<Option<Period>>,
+    #[structopt(
+        long = "trend",
+        help = "show how your pace over this distance developed, according to your history"
+    )]
+    trend: Option<String>,
+    #[structopt(
+        long = "power-curve",
+        help = "write your best time for each distance to this file, as JSON or CSV depending on its extension"
+    )]
+    power_curve: Option<String>,
+    #[structopt(
+        long = "include-history",
+        requires = "power-curve",
+        help = "also take the runs saved in your history into account for --power-curve"
+    )]
+    include_history: bool,
+    #[structopt(
+        long = "tag",
+        help = "also summarise the runs in your history with this tag, and only take those into account everywhere else"
+    )]
+    tag: Option<String>,
+    #[structopt(flatten)]
+    history: HistoryOptions,
+}
+
+#[derive(StructOpt, Debug)]
+struct BandOptions {
+    #[structopt(help = "the distance of your race")]
+    distance: String,
+    #[structopt(help = "the time you want to finish in")]
+    time: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(
+        long = "out",
+        help = "also write the pace band to this file, as PDF or SVG depending on its extension"
+    )]
+    out: Option<String>,
+    #[structopt(
+        long = "round-to",
+        help = "round the pace and the split times to the nearest multiple of this many seconds"
+    )]
+    round_to: Option<f64>,
+}
+
+#[derive(StructOpt, Debug)]
+struct LiveOptions {
+    #[structopt(help = "the distance of each lap, e.g. 400m on a track")]
+    lap_distance: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct PlanOptions {
+    #[structopt(help = "the distance of your goal race")]
+    goal_distance: String,
+    #[structopt(help = "the number of weeks until the race")]
+    weeks: usize,
+    #[structopt(
+        long = "current",
+        help = "the distance you currently run per week, by default half of the planned peak"
+    )]
+    current: Option<String>,
+    #[structopt(
+        long = "recent",
+        help = "a recent run to estimate your current fitness from, e.g. \"10km 52min\""
+    )]
+    recent: Option<String>,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct ProjectOptions {
+    #[structopt(help = "the distance of your runs")]
+    distance: String,
+    #[structopt(help = "the time you want to run the distance in")]
+    target_time: String,
+    #[structopt(
+        long = "result",
+        number_of_values = 2,
+        value_names = &["date", "time"],
+        help = "a result over the distance to use instead of your history, e.g. 2024-03-01 25:30"
+    )]
+    result: Vec<String>,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(flatten)]
+    history: HistoryOptions,
+}
+
+#[derive(StructOpt, Debug)]
+struct TargetOptions {
+    #[structopt(help = "the distance you want to run")]
+    distance: String,
+    #[structopt(help = "the time you want to need for it")]
+    time: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(long = "show-formula", help = "show how the values are calculated")]
+    show_formula: bool,
+    #[structopt(
+        long = "pyramid",
+        help = "plan splits that get faster towards a peak and slower again afterwards"
+    )]
+    pyramid: bool,
+    #[structopt(
+        long = "pyramid-peak",
+        default_value = "0.5",
+        help = "where the peak of the pyramid is, as a fraction of the distance"
+    )]
+    pyramid_peak: f64,
+}
+
+#[derive(StructOpt, Debug)]
+struct VdotOptions {
+    #[structopt(flatten)]
+    run: RunArguments,
+}
+
+#[derive(StructOpt, Debug)]
+struct PaceTableOptions {
+    #[structopt(
+        help = "the pace to convert, per kilometer (or per mile with --miles), e.g. 5:00 or 8:00/mi"
+    )]
+    pace: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct PaceOptions {
+    #[structopt(
+        help = "the pace to calculate with, per kilometer (or per mile with --miles), e.g. 5:00 or 8:00/mi"
+    )]
+    pace: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
+    #[structopt(long = "show-formula", help = "show how the values are calculated")]
+    show_formula: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct IntervalOptions {
+    #[structopt(long = "repeat", help = "how many repetitions you ran")]
+    repeat: usize,
+    #[structopt(long = "rep-distance", help = "the distance of each repetition")]
+    rep_distance: String,
+    #[structopt(long = "rep-time", help = "the time of each repetition")]
+    rep_time: String,
+    #[structopt(long = "rest", help = "the rest between two repetitions")]
+    rest: String,
+    #[structopt(short = "m", long = "miles", help = "use miles as unit of length")]
+    use_miles: bool,
 }
 
 #[derive(Debug)]
@@ -32,269 +995,6215 @@ struct Run {
     time: Time,
 }
 
-impl Run {
-    fn from_options(options: &CommandLineOptions) -> anyhow::Result<Self> {
-        let dist_reg = Regex::new(r"(?P<value>\d+(\.\d*)?)\s*(?P<unit>[[:alpha:]]*)")
-            .expect("distance parsing regex is wrong!");
-        let dist_caps = dist_reg
-            .captures(&options.distance)
-            .with_context(|| "Could not parse distance.")?;
-        let dist_value = dist_caps
-            .name("value")
-            .with_context(|| "Could not find a value for distance.")?
-            .as_str()
-            .parse()
-            .with_context(|| "Could not parse distance value as number.")?;
-        let dist_unit = dist_caps
-            .name("unit")
-            .with_context(|| "Could not find a unit for distance.")?
-            .as_str()
-            .to_lowercase();
-
-        let distance = match &dist_unit[..] {
-            "m" | "meter" | "meters" => Length::new::<meter>(dist_value),
-            "km" | "kilometer" | "kilometers" => Length::new::<kilometer>(dist_value),
-            "mi" | "mile" | "miles" => Length::new::<mile>(dist_value),
-            "yd" | "yard" | "yards" => Length::new::<yard>(dist_value),
-            "ft" | "foot" | "feet" => Length::new::<foot>(dist_value),
-            _ => None.with_context(|| format!("Unknown unit \"{}\".", dist_unit))?,
-        };
+/// The units of time that can be combined in a time like "1h 2min 3s", with the names of their
+/// groups in the time regex and the accepted spellings. Longer spellings come first, so that they
+/// are preferred over their prefixes.
+const TIME_UNITS: [(&str, &[&str]); 4] = [
+    ("days", &["days", "day", "d"]),
+    ("hours", &["hours", "hour", "hrs", "hr", "h"]),
+    ("minutes", &["minutes", "minute", "mins", "min", "m"]),
+    ("seconds", &["seconds", "second", "secs", "sec", "s"]),
+];
+
+/// The units of length with their accepted spellings.
+fn distance_units() -> Vec<(&'static [&'static str], Length)> {
+    vec![
+        (
+            &["cm", "centimeter", "centimeters"],
+            Length::new::<centimeter>(1.0),
+        ),
+        (&["m", "meter", "meters"], Length::new::<meter>(1.0)),
+        (
+            &["km", "kilometer", "kilometers"],
+            Length::new::<kilometer>(1.0),
+        ),
+        (&["in", "inch", "inches"], Length::new::<inch>(1.0)),
+        (&["mi", "mile", "miles"], Length::new::<mile>(1.0)),
+        (&["yd", "yard", "yards"], Length::new::<yard>(1.0)),
+        (&["ft", "foot", "feet"], Length::new::<foot>(1.0)),
+    ]
+}
+
+/// The units of velocity with their accepted spellings.
+fn velocity_units() -> Vec<(&'static [&'static str], Velocity)> {
+    vec![
+        (
+            &["km/h", "kmh", "kph"],
+            Velocity::new::<kilometer_per_hour>(1.0),
+        ),
+        (&["mi/h", "mph"], Velocity::new::<mile_per_hour>(1.0)),
+        (&["m/s", "mps"], Velocity::new::<meter_per_second>(1.0)),
+    ]
+}
+
+/// The pattern of a time with units like "1h 2min 3s". Each value is matched as a number on its
+/// own, so that a decimal fraction cannot be split between two groups.
+fn time_pattern() -> String {
+    format!(
+        r"^\s*{}\s*$",
+        TIME_UNITS
+            .iter()
+            .map(|(group, aliases)| format!(
+                r"((?P<{}>\d+([.,]\d*)?)\s*({}))?",
+                group,
+                aliases.join("|")
+            ))
+            .collect::<Vec<_>>()
+            .join(r"\s*")
+    )
+}
+
+lazy_static! {
+    // The regular expressions are compiled only once, as parsing many runs would otherwise
+    // spend most of its time compiling them again and again.
+    static ref DISTANCE_REGEX: Regex =
+        Regex::new(r"(?P<value>\d+(\.\d*)?)\s*(?P<unit>[[:alpha:]]*)")
+            .expect("distance parsing regex is wrong!");
+    static ref NUMBER_REGEX: Regex =
+        Regex::new(r"^\s*\d+(\.\d*)?\s*$").expect("number parsing regex is wrong!");
+    static ref MASS_REGEX: Regex = Regex::new(r"(?P<value>\d+(\.\d*)?)\s*(?P<unit>[[:alpha:]]*)")
+        .expect("mass parsing regex is wrong!");
+    static ref CLOCK_TIME_REGEX: Regex =
+        Regex::new(r"^\s*((?P<hours>\d+):)?(?P<minutes>\d+):(?P<seconds>\d+([.,]\d*)?)\s*$")
+            .expect("clock time parsing regex is wrong!");
+    static ref TIME_REGEX: Regex =
+        Regex::new(&time_pattern()).expect("time parsing regex is wrong!");
+    static ref VELOCITY_REGEX: Regex =
+        Regex::new(r"^\s*(?P<value>\d+(\.\d*)?)\s*(?P<unit>[[:alpha:]/]+)\s*$")
+            .expect("velocity parsing regex is wrong!");
+    static ref DIGIT_GROUP_SPACE_REGEX: Regex =
+        Regex::new(r"(?P<before>\d)[ \u{a0}\u{202f}](?P<group>\d{3})(?P<after>\D|$)")
+            .expect("digit group parsing regex is wrong!");
+    static ref NEGATIVE_VALUE_REGEX: Regex =
+        Regex::new(r"^-\d").expect("negative value regex is wrong!");
+    static ref GPX_POINT_REGEX: Regex =
+        Regex::new(r"(?s)<trkpt\b(?P<attributes>[^>]*?)(/>|>(?P<body>.*?)</trkpt>)")
+            .expect("GPX track point regex is wrong!");
+    static ref GPX_ATTRIBUTE_REGEX: Regex =
+        Regex::new(r#"\b(?P<name>\w+)\s*=\s*["'](?P<value>[^"']*)["']"#)
+            .expect("GPX attribute regex is wrong!");
+    static ref GPX_ELEMENT_REGEX: Regex =
+        Regex::new(r"(?s)<(?P<name>\w+)>(?P<value>[^<]*)</\w+>")
+            .expect("GPX element regex is wrong!");
+    static ref GPX_TIME_REGEX: Regex = Regex::new(
+        r"^(?P<year>\d{4})-(?P<month>\d{2})-(?P<day>\d{2})T(?P<hour>\d{2}):(?P<minute>\d{2}):(?P<second>\d{2}(\.\d+)?)(Z|(?P<sign>[+-])(?P<offset_hours>\d{2}):?(?P<offset_minutes>\d{2}))?$"
+    )
+    .expect("GPX time regex is wrong!");
+    static ref NAMED_DISTANCE_REGEX: Regex =
+        Regex::new(r"^\s*(?P<value>\d+(\.\d*)?)\s*(?P<name>[[:alpha:]][[:alpha:] ]*?)\s*$")
+            .expect("named distance parsing regex is wrong!");
+}
+
+/// The unicode fraction characters that can be used in distances, like in "½ marathon".
+const UNICODE_FRACTIONS: [(char, f64); 15] = [
+    ('½', 1.0 / 2.0),
+    ('⅓', 1.0 / 3.0),
+    ('⅔', 2.0 / 3.0),
+    ('¼', 1.0 / 4.0),
+    ('¾', 3.0 / 4.0),
+    ('⅕', 1.0 / 5.0),
+    ('⅖', 2.0 / 5.0),
+    ('⅗', 3.0 / 5.0),
+    ('⅘', 4.0 / 5.0),
+    ('⅙', 1.0 / 6.0),
+    ('⅚', 5.0 / 6.0),
+    ('⅛', 1.0 / 8.0),
+    ('⅜', 3.0 / 8.0),
+    ('⅝', 5.0 / 8.0),
+    ('⅞', 7.0 / 8.0),
+];
+
+/// Replaces unicode fraction characters with decimals, so that "1½ mi" becomes "1.5 mi" and
+/// "¼ mi" becomes "0.25 mi".
+fn replace_unicode_fractions(value: &str) -> String {
+    let mut replaced = String::new();
+    for character in value.chars() {
+        match UNICODE_FRACTIONS
+            .iter()
+            .find(|(fraction, _)| *fraction == character)
+        {
+            Some((_, fraction_value)) => {
+                let decimal = fraction_value.to_string();
+                if replaced.ends_with(|c: char| c.is_ascii_digit()) {
+                    // Only the part from the decimal point on, as the whole number is already there.
+                    replaced.push_str(&decimal[1..]);
+                } else {
+                    replaced.push_str(&decimal);
+                }
+            }
+            None => replaced.push(character),
+        }
+    }
+    replaced
+}
+
+/// Finds the standard distance with the given name, like "marathon" or "5 mi".
+fn find_named_distance(name: &str) -> Option<Length> {
+    standard_distances(false)
+        .into_iter()
+        .chain(standard_distances(true))
+        .find(|named| named.name == name)
+        .map(|named| named.distance)
+}
+
+/// Allows a comma as decimal separator, as it is common in many locales.
+/// A single comma is read as decimal separator unless there is also a dot,
+/// otherwise commas are considered thousands separators and dropped.
+fn normalize_decimal_separator(value: &str) -> String {
+    if value.matches(',').count() == 1 && !value.contains('.') {
+        value.replace(',', ".")
+    } else {
+        value.replace(',', "")
+    }
+}
+
+/// Removes spaces that separate groups of thousands, like in "10 000 m", so that they are not
+/// confused with the space between number and unit.
+fn remove_digit_group_spaces(value: &str) -> String {
+    let mut value = value.to_string();
+    loop {
+        // Adjacent groups share a digit, so each pass can only join every other one.
+        let joined = DIGIT_GROUP_SPACE_REGEX
+            .replace_all(&value, "$before$group$after")
+            .into_owned();
+        if joined == value {
+            return value;
+        }
+        value = joined;
+    }
+}
+
+fn parse_distance(input: &str) -> anyhow::Result<Length> {
+    let input = replace_unicode_fractions(input);
+    let name = input.trim().to_lowercase();
+    if let Some(distance) = find_named_distance(&name) {
+        return Ok(distance);
+    }
+    // A multiple of a standard distance, like "0.5 marathon" or "½ marathon".
+    if let Some(caps) = NAMED_DISTANCE_REGEX.captures(&name) {
+        if let Some(distance) = find_named_distance(&caps["name"]) {
+            let factor: f64 = caps["value"]
+                .parse()
+                .with_context(|| "Could not parse distance value as number.")?;
+            return Ok(distance * factor);
+        }
+    }
+
+    let distance_input = normalize_decimal_separator(&remove_digit_group_spaces(&input));
+    let dist_caps = DISTANCE_REGEX
+        .captures(&distance_input)
+        .with_context(|| "Could not parse distance.")?;
+    let dist_value: f64 = dist_caps
+        .name("value")
+        .with_context(|| "Could not find a value for distance.")?
+        .as_str()
+        .parse()
+        .with_context(|| "Could not parse distance value as number.")?;
+    let dist_unit = dist_caps
+        .name("unit")
+        .with_context(|| "Could not find a unit for distance.")?
+        .as_str()
+        .to_lowercase();
+
+    let (_, unit) = distance_units()
+        .into_iter()
+        .find(|(aliases, _)| aliases.contains(&dist_unit.as_str()))
+        .with_context(|| format!("Unknown unit \"{}\".", dist_unit))?;
+
+    Ok(unit * dist_value)
+}
+
+/// Below this value, a distance without unit is read as kilometers by `--smart-units`,
+/// otherwise as meters.
+const SMART_UNITS_KILOMETER_LIMIT: f64 = 100.0;
+
+/// Like `parse_distance`, but guesses the unit if only a number is given.
+fn parse_distance_with_smart_units(input: &str) -> anyhow::Result<Length> {
+    let distance_input = normalize_decimal_separator(&remove_digit_group_spaces(input));
+    if !NUMBER_REGEX.is_match(&distance_input) {
+        return parse_distance(input);
+    }
+
+    let value: f64 = distance_input
+        .trim()
+        .parse()
+        .with_context(|| "Could not parse distance value as number.")?;
+    if value < SMART_UNITS_KILOMETER_LIMIT {
+        Ok(Length::new::<kilometer>(value))
+    } else {
+        Ok(Length::new::<meter>(value))
+    }
+}
+
+fn parse_mass(input: &str) -> anyhow::Result<Mass> {
+    let mass_input = normalize_decimal_separator(input);
+    let mass_caps = MASS_REGEX
+        .captures(&mass_input)
+        .with_context(|| "Could not parse weight.")?;
+    let mass_value = mass_caps
+        .name("value")
+        .with_context(|| "Could not find a value for weight.")?
+        .as_str()
+        .parse()
+        .with_context(|| "Could not parse weight value as number.")?;
+    let mass_unit = mass_caps
+        .name("unit")
+        .with_context(|| "Could not find a unit for weight.")?
+        .as_str()
+        .to_lowercase();
+
+    let mass = match &mass_unit[..] {
+        "kg" | "kilogram" | "kilograms" => Mass::new::<kilogram>(mass_value),
+        "lb" | "lbs" | "pound" | "pounds" => Mass::new::<pound>(mass_value),
+        _ => None.with_context(|| format!("Unknown unit \"{}\".", mass_unit))?,
+    };
+
+    Ok(mass)
+}
+
+/// Parses a distance that may be preceded by a sign, like a net elevation change of "-120m".
+fn parse_signed_distance(input: &str) -> anyhow::Result<Length> {
+    let input = input.trim();
+    match input.strip_prefix('-') {
+        Some(magnitude) => Ok(-parse_distance(magnitude)?),
+        None => parse_distance(input.trim_start_matches('+')),
+    }
+}
+
+fn parse_velocity(input: &str) -> anyhow::Result<Velocity> {
+    let velocity_input = normalize_decimal_separator(input);
+    let velocity_caps = VELOCITY_REGEX
+        .captures(&velocity_input)
+        .with_context(|| "Could not parse velocity.")?;
+    let velocity_value: f64 = velocity_caps["value"]
+        .parse()
+        .with_context(|| "Could not parse velocity value as number.")?;
+    let velocity_unit = velocity_caps["unit"].to_lowercase();
+
+    let velocity = velocity_units()
+        .into_iter()
+        .find(|(aliases, _)| aliases.contains(&velocity_unit.as_str()))
+        .map(|(_, unit)| unit * velocity_value)
+        .with_context(|| format!("Unknown unit \"{}\".", velocity_unit))?;
+    if velocity <= Velocity::new::<meter_per_second>(0.0) {
+        return Err(anyhow!("The velocity must be greater than zero."));
+    }
+
+    Ok(velocity)
+}
+
+/// Allows a leading "+" in front of a value that has to be positive, but rejects a "-" instead
+/// of silently ignoring it. An empty value is rejected right away, too, as the time regex would
+/// otherwise accept it.
+fn strip_positive_sign<'a>(input: &'a str, what: &str) -> anyhow::Result<&'a str> {
+    let input = input.trim();
+    if input.starts_with('-') {
+        return Err(anyhow!(
+            "The {} must be positive, but \"{}\" is negative.",
+            what,
+            input
+        ));
+    }
+    let magnitude = input.trim_start_matches('+').trim_start();
+    if magnitude.is_empty() {
+        Err(anyhow!("The {} must not be empty.", what))
+    } else {
+        Ok(magnitude)
+    }
+}
+
+/// Parses a pace, i.e. the time needed for one kilometer (or one mile), like "4:30".
+/// An explicit unit like in "4:30/km" or "7:15/mi" takes precedence over the default one.
+fn parse_pace(input: &str, use_miles: bool) -> anyhow::Result<Velocity> {
+    let (time, pace_unit) = match input.rfind('/') {
+        Some(separator) => (
+            &input[..separator],
+            parse_pace_unit(&input[separator + 1..]).with_context(|| {
+                format!("Unknown pace unit \"{}\".", input[separator + 1..].trim())
+            })?,
+        ),
+        None => (input, unit_distance(use_miles)),
+    };
+    let time = parse_time(time)?;
+    if time <= Time::new::<second>(0.0) {
+        return Err(anyhow!("The pace must be longer than zero."));
+    }
+    Ok(pace_unit / time)
+}
+
+/// Parses the goal given as time and distance, like "3:30:00 marathon", if there is one.
+fn parse_goal(options: &GoalOptions) -> anyhow::Result<Option<Run>> {
+    if let Some(days) = options.goal_days {
+        if days <= 0.0 {
+            return Err(anyhow!(
+                "The number of days until your goal must be positive."
+            ));
+        }
+    }
+    match (options.goal.first(), options.goal.get(1)) {
+        (Some(time), Some(distance)) => Ok(Some(Run {
+            distance: parse_distance(distance)?,
+            time: parse_time(time)?,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Parses a run given as distance and time in one string, like "10km 52min" or "10 km 52:00".
+fn parse_run(input: &str) -> anyhow::Result<Run> {
+    input
+        .match_indices(char::is_whitespace)
+        .filter_map(|(separator, _)| {
+            let distance = parse_distance(&input[..separator]).ok()?;
+            let time = parse_time(&input[separator..]).ok()?;
+            Some(Run { distance, time })
+        })
+        .next()
+        .with_context(|| format!("\"{}\" is not of the form <distance> <time>.", input))
+}
+
+/// Parses a part of a run given as "<distance>:<time>", like "1km:6:00".
+fn parse_segment(input: &str) -> anyhow::Result<Run> {
+    let separator = input
+        .find(':')
+        .with_context(|| format!("\"{}\" is not of the form <distance>:<time>.", input))?;
+    let distance = parse_distance(&input[..separator])?;
+    let time = parse_time(&input[separator + 1..])?;
+    Ok(Run { distance, time })
+}
+
+/// Parses a time given either like a clock ("1:05:00") or with units ("1h5min"), so that all
+/// spellings of the same time result in the same value.
+fn parse_time(input: &str) -> anyhow::Result<Time> {
+    let time_caps = CLOCK_TIME_REGEX
+        .captures(input)
+        .or_else(|| TIME_REGEX.captures(input))
+        .with_context(|| "Could not parse time.")?;
+
+    if !["days", "hours", "minutes", "seconds"]
+        .iter()
+        .map(|g| time_caps.name(g))
+        .any(|m| m.is_some())
+    {
+        return Err(anyhow!(
+            "No days, no hours, no minutes, and no seconds given."
+        ));
+    }
+
+    let group_to_value = |group| {
+        time_caps.name(group).map_or(Ok(0.0), |m| {
+            normalize_decimal_separator(m.as_str())
+                .parse()
+                .with_context(|| format!("\"{}\" is not a number", m.as_str()))
+        })
+    };
+    let days = group_to_value("days").with_context(|| "Could not parse days value as number.")?;
+    let hours =
+        group_to_value("hours").with_context(|| "Could not parse hours value as number.")?;
+    let minutes =
+        group_to_value("minutes").with_context(|| "Could not parse minutes value as number.")?;
+    let seconds =
+        group_to_value("seconds").with_context(|| "Could not parse seconds value as number.")?;
+
+    Ok(Time::new::<day>(days)
+        + Time::new::<hour>(hours)
+        + Time::new::<minute>(minutes)
+        + Time::new::<second>(seconds))
+}
+
+impl Run {
+    /// Reads the run from the command line arguments, where a pace is given per kilometer or,
+    /// if `use_miles` is set, per mile.
+    fn from_options(options: &RunArguments, use_miles: bool) -> anyhow::Result<Self> {
+        let parse_distance = |input: &str| {
+            if options.smart_units {
+                parse_distance_with_smart_units(input)
+            } else {
+                parse_distance(input)
+            }
+        };
+
+        if let Some(activity) = read_activity(options)? {
+            return Ok(activity.run());
+        }
+        let distance = options
+            .distance
+            .as_ref()
+            .with_context(|| "Either a distance, a FIT file or a GPX file is needed.")?;
+
+        let run = match (&options.time, &options.pace) {
+            (Some(time), _) => Run {
+                distance: parse_distance(strip_positive_sign(distance, "distance")?)?,
+                time: parse_time(strip_positive_sign(time, "time")?)?,
+            },
+            (None, Some(pace)) => {
+                let velocity = parse_pace(pace, use_miles)
+                    .with_context(|| "Could not understand the pace.")?;
+                // Exactly one of distance and time is known, so find out which one it is.
+                let known = strip_positive_sign(distance, "distance or time")?;
+                match parse_distance(known) {
+                    Ok(distance) => Run {
+                        distance,
+                        time: distance / velocity,
+                    },
+                    Err(distance_error) => match parse_time(known) {
+                        Ok(time) => Run {
+                            distance: velocity * time,
+                            time,
+                        },
+                        Err(_) => {
+                            return Err(distance_error.context(format!(
+                                "\"{}\" is neither a distance nor a time.",
+                                distance
+                            )))
+                        }
+                    },
+                }
+            }
+            (None, None) => return Err(anyhow!("Either a time or a pace is needed.")),
+        };
+        run.validated()
+    }
+
+    /// Rejects runs without a positive distance and time, whose velocity would be meaningless.
+    fn validated(self) -> anyhow::Result<Self> {
+        if self.distance <= Length::new::<meter>(0.0) {
+            return Err(anyhow!("The distance must be longer than zero."));
+        }
+        if self.time <= Time::new::<second>(0.0) {
+            return Err(anyhow!("The time must be longer than zero."));
+        }
+        Ok(self)
+    }
+
+    fn average_velocity(&self) -> Velocity {
+        return self.distance / self.time;
+    }
+
+    /// Scales the time to another distance at the same pace. Predictions go through
+    /// `predicted_time`, so that they follow the chosen model.
+    fn time_for_distance(&self, other_distance: &Length) -> Time {
+        *other_distance / self.distance * self.time
+    }
+
+    /// Estimates the time for another distance with Peter Riegel's formula, which accounts
+    /// for getting slower over longer distances.
+    fn riegel_time_for_distance(&self, other_distance: &Length) -> Time {
+        self.time
+            * (*other_distance / self.distance)
+                .get::<ratio>()
+                .powf(RIEGEL_EXPONENT)
+    }
+
+    /// Estimates the time for another distance with Dave Cameron's formula, which slows down
+    /// more than Riegel's for short distances and less for long ones.
+    fn cameron_time_for_distance(&self, other_distance: &Length) -> Time {
+        let factor = |distance: &Length| {
+            let miles = distance.get::<mile>();
+            13.49681 - 0.048_865 * miles + 2.438_936 / miles.powf(0.7905)
+        };
+        self.time * (*other_distance / self.distance).get::<ratio>() * factor(&self.distance)
+            / factor(other_distance)
+    }
+
+    /// Estimates the time for another distance with the given model.
+    fn predicted_time(&self, other_distance: &Length, model: PredictionModel) -> Time {
+        match model {
+            PredictionModel::Linear => self.time_for_distance(other_distance),
+            PredictionModel::Riegel => self.riegel_time_for_distance(other_distance),
+            PredictionModel::Cameron => self.cameron_time_for_distance(other_distance),
+        }
+    }
+
+    /// Estimates the VDOT, i.e. the VO2max implied by this performance, using the formula by
+    /// Jack Daniels and Jimmy Gilbert.
+    fn vdot(&self) -> f64 {
+        let v = self.average_velocity().get::<meter_per_second>() * 60.0;
+        let t = self.time.get::<minute>();
+        let oxygen_cost = -4.60 + 0.182_258 * v + 0.000_104 * v * v;
+        let fraction_of_max =
+            0.8 + 0.189_439_3 * (-0.012_778 * t).exp() + 0.298_955_8 * (-0.193_260_5 * t).exp();
+        oxygen_cost / fraction_of_max
+    }
+
+    /// Finds the time for a distance that results in the given VDOT, by bisection as the VDOT
+    /// decreases the longer the run takes.
+    fn for_vdot(distance: &Length, vdot: f64) -> Run {
+        let fastest = *distance / Velocity::new::<meter_per_second>(VDOT_SEARCH_MAX_VELOCITY);
+        let slowest = *distance / Velocity::new::<meter_per_second>(VDOT_SEARCH_MIN_VELOCITY);
+        let (mut fast, mut slow) = (fastest, slowest);
+        for _ in 0..VDOT_SEARCH_ITERATIONS {
+            let middle = (fast + slow) / 2.0;
+            let candidate = Run {
+                distance: *distance,
+                time: middle,
+            };
+            if candidate.vdot() > vdot {
+                fast = middle;
+            } else {
+                slow = middle;
+            }
+        }
+        Run {
+            distance: *distance,
+            time: (fast + slow) / 2.0,
+        }
+    }
+
+    /// The part of the run that remains when the given segments (like warmup and cooldown)
+    /// are taken away.
+    fn main_set(&self, segments: &[Run]) -> anyhow::Result<Run> {
+        let mut main = Run {
+            distance: self.distance,
+            time: self.time,
+        };
+        for segment in segments {
+            main.distance -= segment.distance;
+            main.time -= segment.time;
+        }
+        if main.distance <= Length::new::<meter>(0.0) || main.time <= Time::new::<second>(0.0) {
+            return Err(anyhow!(
+                "Warmup and cooldown must not make up the whole distance or time of your run."
+            ));
+        }
+        Ok(main)
+    }
+
+    /// Splits the run into parts of the given length (the last one may be shorter) whose
+    /// velocity ramps up linearly towards the peak (given as fraction of the distance) and
+    /// down again afterwards, such that the total time stays the same.
+    fn pyramid_splits(&self, split_length: &Length, peak: f64) -> Vec<Run> {
+        let count = ((self.distance / *split_length).get::<ratio>() - 1e-9).ceil() as usize;
+        let mut splits: Vec<Run> = (0..count)
+            .map(|i| {
+                let start = *split_length * i as f64;
+                let end = (*split_length * (i + 1) as f64).min(self.distance);
+                let middle = ((start + end) / 2.0 / self.distance).get::<ratio>();
+                let closeness_to_peak = if middle < peak {
+                    middle / peak
+                } else {
+                    (1.0 - middle) / (1.0 - peak)
+                };
+                Run {
+                    distance: end - start,
+                    time: self.time_for_distance(&(end - start))
+                        / (1.0 + PYRAMID_PEAK_SPEEDUP * closeness_to_peak),
+                }
+            })
+            .collect();
+
+        let planned_time = splits
+            .iter()
+            .fold(Time::new::<second>(0.0), |total, split| total + split.time);
+        for split in &mut splits {
+            split.time *= (self.time / planned_time).get::<ratio>();
+        }
+        splits
+    }
+
+    /// Estimates how long the same effort would have taken on flat ground, given the net
+    /// elevation change of the run. Climbing makes the equivalent time shorter, descending
+    /// makes it longer.
+    fn equivalent_flat_time(&self, elevation: &Length) -> Time {
+        self.time / self.grade_effort(elevation)
+    }
+
+    /// The effort of the run relative to running the same pace on flat ground.
+    fn grade_effort(&self, elevation: &Length) -> f64 {
+        let grade = (*elevation / self.distance).get::<percent>();
+        if grade >= 0.0 {
+            1.0 + UPHILL_EFFORT_PER_PERCENT_GRADE * grade
+        } else {
+            1.0 + DOWNHILL_EFFORT_PER_PERCENT_GRADE * grade.max(-MAX_BENEFICIAL_DOWNHILL_GRADE)
+        }
+    }
+}
+
+/// The distance (in m) of a repetition on the track.
+const REPETITION_DISTANCE: f64 = 400.0;
+/// Daniels' repetition pace is about the race pace for one mile (in m) at the same VDOT.
+const REPETITION_PACE_DISTANCE: f64 = 1609.344;
+
+/// The slowest velocity (in m/s) considered when searching the time for a VDOT.
+const VDOT_SEARCH_MIN_VELOCITY: f64 = 0.5;
+/// The fastest velocity (in m/s) considered when searching the time for a VDOT.
+const VDOT_SEARCH_MAX_VELOCITY: f64 = 15.0;
+/// The number of bisection steps when searching the time for a VDOT.
+const VDOT_SEARCH_ITERATIONS: usize = 100;
+
+/// The exponent in Riegel's formula for how the time grows with the distance.
+const RIEGEL_EXPONENT: f64 = 1.06;
+
+/// How much faster than at the start and the end the peak of a pyramid workout is run.
+const PYRAMID_PEAK_SPEEDUP: f64 = 0.1;
+
+/// Additional effort per percent of uphill grade, a common rule of thumb for grade adjusted pace.
+const UPHILL_EFFORT_PER_PERCENT_GRADE: f64 = 0.033;
+/// Saved effort per percent of downhill grade, descending helps less than climbing hurts.
+const DOWNHILL_EFFORT_PER_PERCENT_GRADE: f64 = 0.018;
+/// Steeper descents than this (in percent) do not make running any easier.
+const MAX_BENEFICIAL_DOWNHILL_GRADE: f64 = 10.0;
+
+/// The body mass index (in kg/m²) used as reference for the body-mass adjusted score.
+const REFERENCE_BODY_MASS_INDEX: f64 = 22.0;
+
+fn body_mass_index(weight: &Mass, height: &Length) -> f64 {
+    weight.get::<kilogram>() / (height.get::<meter>() * height.get::<meter>())
+}
+
+/// A rough heuristic to account for the fact that heavier runners work harder at the same
+/// pace: the average velocity in km/h, scaled by the body mass index relative to a typical one.
+/// This is meant for fun comparisons and is not backed by any physiological model.
+fn body_mass_adjusted_score(run: &Run, weight: &Mass, height: &Length) -> f64 {
+    run.average_velocity().get::<kilometer_per_hour>() * body_mass_index(weight, height)
+        / REFERENCE_BODY_MASS_INDEX
+}
+
+/// The kilocalories burned per kilogram of body weight and kilometer, a common rule of thumb.
+const KILOCALORIES_PER_KILOGRAM_AND_KILOMETER: f64 = 1.0;
+/// The oxygen uptake (in ml/kg/min) per meter per minute of horizontal running, according to
+/// the ACSM metabolic equation.
+const ACSM_HORIZONTAL_OXYGEN_COST: f64 = 0.2;
+/// The oxygen uptake (in ml/kg/min) per meter per minute of vertical climbing while running.
+const ACSM_VERTICAL_OXYGEN_COST: f64 = 0.9;
+/// The oxygen uptake (in ml/kg/min) at rest.
+const RESTING_OXYGEN_UPTAKE: f64 = 3.5;
+/// The kilocalories burned per liter of oxygen.
+const KILOCALORIES_PER_LITER_OXYGEN: f64 = 5.0;
+
+impl Run {
+    /// Estimates the kilocalories burned using the rule of thumb of one per kilogram and
+    /// kilometer.
+    fn simple_kilocalories(&self, weight: &Mass) -> f64 {
+        KILOCALORIES_PER_KILOGRAM_AND_KILOMETER
+            * weight.get::<kilogram>()
+            * self.distance.get::<kilometer>()
+    }
+
+    /// Estimates the kilocalories burned with the ACSM metabolic equation for running, which
+    /// takes the speed and the grade (from the net elevation change) into account.
+    fn acsm_kilocalories(&self, weight: &Mass, elevation: &Length) -> f64 {
+        let speed = self.average_velocity().get::<meter_per_second>() * 60.0;
+        // The equation is only meant for level and uphill running.
+        let grade = (*elevation / self.distance).get::<ratio>().max(0.0);
+        let oxygen_uptake = ACSM_HORIZONTAL_OXYGEN_COST * speed
+            + ACSM_VERTICAL_OXYGEN_COST * speed * grade
+            + RESTING_OXYGEN_UPTAKE;
+        oxygen_uptake / 1000.0
+            * weight.get::<kilogram>()
+            * self.time.get::<minute>()
+            * KILOCALORIES_PER_LITER_OXYGEN
+    }
+}
+
+/// The body weight (in kg) assumed for cross-training equivalents if none is given.
+const REFERENCE_BODY_WEIGHT: f64 = 70.0;
+/// The metabolic energy (in J) needed per kilogram and meter of running, roughly 1 kcal/kg/km.
+const RUNNING_ENERGY_PER_KILOGRAM_AND_METER: f64 = 4.184;
+/// The share of the metabolic power that ends up on the pedals of a bike erg, approximately.
+const CYCLING_EFFICIENCY: f64 = 0.23;
+/// How much power an indoor rower shows compared to a bike erg at the same effort, approximately.
+const ROWING_TO_CYCLING_POWER: f64 = 0.9;
+/// The constant of the Concept2 formula relating power (in W) and pace (in s per 500 m).
+const ROWING_PACE_CONSTANT: f64 = 2.8;
+
+impl Run {
+    /// Estimates the power on a bike erg (in W) that takes the same effort as this run, using
+    /// crude conversion factors.
+    fn equivalent_cycling_power(&self, weight: &Mass) -> f64 {
+        RUNNING_ENERGY_PER_KILOGRAM_AND_METER
+            * self.average_velocity().get::<meter_per_second>()
+            * weight.get::<kilogram>()
+            * CYCLING_EFFICIENCY
+    }
+
+    /// Estimates the 500 m split on an indoor rower that takes the same effort as this run,
+    /// using crude conversion factors.
+    fn equivalent_rowing_split(&self, weight: &Mass) -> Time {
+        let power = self.equivalent_cycling_power(weight) * ROWING_TO_CYCLING_POWER;
+        Time::new::<second>(500.0 * (ROWING_PACE_CONSTANT / power).cbrt())
+    }
+}
+
+struct NamedLength {
+    name: String,
+    distance: Length,
+}
+
+struct NamedVelocity {
+    name: String,
+    velocity: Velocity,
+    /// The conditions of the performance that matter when comparing with it.
+    note: Option<&'static str>,
+}
+
+struct NamedRun {
+    name: String,
+    run: Run,
+}
+
+/// A training zone, given as range of paces in percent of the threshold pace.
+struct PaceZone {
+    name: String,
+    slowest: Option<f64>,
+    fastest: Option<f64>,
+}
+
+/// The training zones following Joe Friel's percentages of the threshold pace.
+fn pace_zones() -> Vec<PaceZone> {
+    vec![
+        PaceZone {
+            name: String::from("1 (recovery)"),
+            slowest: None,
+            fastest: Some(129.0),
+        },
+        PaceZone {
+            name: String::from("2 (aerobic)"),
+            slowest: Some(129.0),
+            fastest: Some(114.0),
+        },
+        PaceZone {
+            name: String::from("3 (tempo)"),
+            slowest: Some(113.0),
+            fastest: Some(106.0),
+        },
+        PaceZone {
+            name: String::from("4 (sub-threshold)"),
+            slowest: Some(105.0),
+            fastest: Some(101.0),
+        },
+        PaceZone {
+            name: String::from("5a (super-threshold)"),
+            slowest: Some(100.0),
+            fastest: Some(97.0),
+        },
+        PaceZone {
+            name: String::from("5b (aerobic capacity)"),
+            slowest: Some(96.0),
+            fastest: Some(90.0),
+        },
+        PaceZone {
+            name: String::from("5c (anaerobic capacity)"),
+            slowest: Some(90.0),
+            fastest: None,
+        },
+    ]
+}
+
+/// The world records at the standard distances, with the mile instead of the kilometer when
+/// using miles.
+fn world_records(use_miles: bool) -> Vec<NamedRun> {
+    let mut records = vec![
+        NamedRun {
+            name: String::from("100 m"),
+            run: Run {
+                distance: Length::new::<meter>(100.0),
+                time: Time::new::<second>(9.58),
+            },
+        },
+        NamedRun {
+            name: String::from("1 km"),
+            run: Run {
+                distance: Length::new::<kilometer>(1.0),
+                time: Time::new::<minute>(2.0) + Time::new::<second>(11.96),
+            },
+        },
+        NamedRun {
+            name: String::from("5 km"),
+            run: Run {
+                distance: Length::new::<kilometer>(5.0),
+                time: Time::new::<minute>(12.0) + Time::new::<second>(37.35),
+            },
+        },
+        NamedRun {
+            name: String::from("10 km"),
+            run: Run {
+                distance: Length::new::<kilometer>(10.0),
+                time: Time::new::<minute>(26.0) + Time::new::<second>(17.53),
+            },
+        },
+        NamedRun {
+            name: String::from("half marathon"),
+            run: Run {
+                distance: Length::new::<kilometer>(21.0975),
+                time: Time::new::<minute>(58.0) + Time::new::<second>(1.0),
+            },
+        },
+        NamedRun {
+            name: String::from("marathon"),
+            run: Run {
+                distance: Length::new::<kilometer>(42.195),
+                time: Time::new::<hour>(2.0) + Time::new::<minute>(1.0) + Time::new::<second>(39.0),
+            },
+        },
+    ];
+    if use_miles {
+        records[1] = NamedRun {
+            name: String::from("1 mi"),
+            run: Run {
+                distance: Length::new::<mile>(1.0),
+                time: Time::new::<minute>(3.0) + Time::new::<second>(43.13),
+            },
+        };
+    }
+    records
+}
+
+fn closest_world_record(distance: &Length) -> NamedRun {
+    closest_by_distance(world_records(false), distance).expect("there are no world records!")
+}
+
+fn closest_by_distance(runs: Vec<NamedRun>, distance: &Length) -> Option<NamedRun> {
+    let deviation = |named: &NamedRun| (named.run.distance - *distance).abs().get::<meter>();
+    runs.into_iter()
+        .min_by(|a, b| deviation(a).partial_cmp(&deviation(b)).unwrap())
+}
+
+/// How much faster the men's world records are than the women's ones at the standard distances,
+/// relative to the women's velocity.
+fn gender_gaps() -> Vec<(Length, f64)> {
+    vec![
+        (Length::new::<meter>(100.0), 0.095),
+        (Length::new::<kilometer>(1.0), 0.11),
+        (Length::new::<kilometer>(5.0), 0.12),
+        (Length::new::<kilometer>(10.0), 0.11),
+        (Length::new::<kilometer>(21.0975), 0.10),
+        (Length::new::<kilometer>(42.195), 0.11),
+    ]
+}
+
+/// The gender gap for the standard distance closest to the given one.
+fn gender_gap(distance: &Length) -> f64 {
+    let deviation = |gap: &(Length, f64)| (gap.0 - *distance).abs().get::<meter>();
+    gender_gaps()
+        .into_iter()
+        .min_by(|a, b| deviation(a).partial_cmp(&deviation(b)).unwrap())
+        .expect("there are no gender gaps!")
+        .1
+}
+
+/// The number of characters of a progress bar.
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+/// Prints a bar that is filled according to the fraction of the race already completed.
+fn print_progress_bar(completed: f64) {
+    let filled = ((completed * PROGRESS_BAR_WIDTH as f64).round() as usize).min(PROGRESS_BAR_WIDTH);
+    report!(
+        "[{}{}] {:.0}%",
+        "█".repeat(filled).green(),
+        "░".repeat(PROGRESS_BAR_WIDTH - filled).dimmed(),
+        completed * 100.0
+    );
+}
+
+/// Running this many times faster than the threshold pace (about a mile race) counts as the
+/// highest effort.
+const EFFORT_MAX_PACE_INTENSITY: f64 = 1.15;
+/// At this fraction of the maximum heart rate (an easy walk), the effort counts as none.
+const EFFORT_MIN_HEART_RATE_FRACTION: f64 = 0.5;
+/// How much the pace counts for the effort score when the heart rate is known as well.
+const EFFORT_PACE_WEIGHT: f64 = 0.6;
+
+/// Combines the grade adjusted velocity relative to the threshold and the heart rate relative
+/// to the maximum into a score from 0 to 100, using whichever of them is known.
+fn effort_score(pace_intensity: Option<f64>, heart_rate_fraction: Option<f64>) -> Option<f64> {
+    let bounded = |score: f64| f64::max(score.min(100.0), 0.0);
+    let pace_score =
+        pace_intensity.map(|intensity| bounded(100.0 * intensity / EFFORT_MAX_PACE_INTENSITY));
+    let heart_rate_score = heart_rate_fraction.map(|fraction| {
+        bounded(
+            100.0 * (fraction - EFFORT_MIN_HEART_RATE_FRACTION)
+                / (1.0 - EFFORT_MIN_HEART_RATE_FRACTION),
+        )
+    });
+    match (pace_score, heart_rate_score) {
+        (Some(pace), Some(heart_rate)) => {
+            Some(EFFORT_PACE_WEIGHT * pace + (1.0 - EFFORT_PACE_WEIGHT) * heart_rate)
+        }
+        (score, None) | (None, score) => score,
+    }
+}
+
+/// Up to this age, runners are not expected to slow down with age.
+const AGE_GRADE_PEAK_AGE: f64 = 30.0;
+/// From this age on, runners slow down faster with age.
+const AGE_GRADE_LATE_AGE: f64 = 60.0;
+
+/// Roughly which fraction of their peak velocity runners of the given age can reach, decreasing
+/// by 0.55% per year after the peak age and by 1.2% per year from the late age on.
+fn age_grade_factor(age: u32) -> f64 {
+    let age = f64::from(age);
+    let early_years = (age.min(AGE_GRADE_LATE_AGE) - AGE_GRADE_PEAK_AGE).max(0.0);
+    let late_years = (age - AGE_GRADE_LATE_AGE).max(0.0);
+    (1.0 - 0.0055 * early_years - 0.012 * late_years).max(0.1)
+}
+
+/// Runs faster than the closest world record by more than this factor are considered
+/// implausible.
+const MAX_PLAUSIBLE_WR_RATIO: f64 = 1.05;
+/// Runs slower than this velocity (in km/h, a slow walk) are considered implausible.
+const MIN_PLAUSIBLE_VELOCITY: f64 = 3.0;
+
+/// A likely mistake in the input, with a code for programs and a message for humans.
+#[derive(Serialize)]
+struct Warning {
+    code: &'static str,
+    message: String,
+}
+
+/// Tells what is wrong with the run if its velocity is far outside what humans do for its
+/// distance, which usually means that digits were mixed up.
+fn implausibility(run: &Run) -> Option<Warning> {
+    let record = closest_world_record(&run.distance);
+    let wr_ratio = (run.average_velocity() / record.run.average_velocity()).get::<ratio>();
+    if wr_ratio > MAX_PLAUSIBLE_WR_RATIO {
+        Some(Warning {
+            code: "faster-than-world-record",
+            message: format!(
+                "This would be {:.0}% faster than the {} world record, please check your distance and time.",
+                (wr_ratio - 1.0) * 100.0,
+                record.name
+            ),
+        })
+    } else if run.average_velocity() < Velocity::new::<kilometer_per_hour>(MIN_PLAUSIBLE_VELOCITY) {
+        Some(Warning {
+            code: "slower-than-walking",
+            message: format!(
+                "This would be slower than {} km/h, please check your distance and time.",
+                MIN_PLAUSIBLE_VELOCITY
+            ),
+        })
+    } else {
+        None
+    }
+}
+
+/// The coefficients of the World Athletics scoring tables for a distance, which give
+/// points = factor × (zero time − time)² with the time in seconds.
+struct PointsCoefficients {
+    distance: Length,
+    factor: f64,
+    zero_time: Time,
+}
+
+/// Approximate coefficients of the men's World Athletics scoring tables for the supported
+/// distances.
+fn points_coefficients() -> Vec<PointsCoefficients> {
+    vec![
+        PointsCoefficients {
+            distance: Length::new::<meter>(100.0),
+            factor: 24.63,
+            zero_time: Time::new::<second>(17.0),
+        },
+        PointsCoefficients {
+            distance: Length::new::<kilometer>(5.0),
+            factor: 2.5597e-3,
+            zero_time: Time::new::<second>(1470.0),
+        },
+        PointsCoefficients {
+            distance: Length::new::<kilometer>(10.0),
+            factor: 5.3046e-4,
+            zero_time: Time::new::<second>(3143.0),
+        },
+        PointsCoefficients {
+            distance: Length::new::<kilometer>(21.0975),
+            factor: 1.2986e-4,
+            zero_time: Time::new::<second>(6645.0),
+        },
+        PointsCoefficients {
+            distance: Length::new::<kilometer>(42.195),
+            factor: 2.8456e-5,
+            zero_time: Time::new::<second>(14058.0),
+        },
+    ]
+}
+
+/// The approximate World Athletics points of the run, if its distance is supported.
+fn world_athletics_points(run: &Run) -> Option<f64> {
+    points_coefficients()
+        .into_iter()
+        .find(|coefficients| is_same_distance(&coefficients.distance, &run.distance))
+        .map(|coefficients| {
+            let margin = (coefficients.zero_time - run.time).get::<second>().max(0.0);
+            (coefficients.factor * margin * margin).floor()
+        })
+}
+
+/// The median finish times of typical race fields.
+fn typical_finish_times() -> Vec<NamedRun> {
+    vec![
+        NamedRun {
+            name: String::from("5 km"),
+            run: Run {
+                distance: Length::new::<kilometer>(5.0),
+                time: Time::new::<minute>(35.0),
+            },
+        },
+        NamedRun {
+            name: String::from("10 km"),
+            run: Run {
+                distance: Length::new::<kilometer>(10.0),
+                time: Time::new::<hour>(1.0) + Time::new::<minute>(2.0),
+            },
+        },
+        NamedRun {
+            name: String::from("half marathon"),
+            run: Run {
+                distance: Length::new::<kilometer>(21.0975),
+                time: Time::new::<hour>(2.0) + Time::new::<minute>(12.0),
+            },
+        },
+        NamedRun {
+            name: String::from("marathon"),
+            run: Run {
+                distance: Length::new::<kilometer>(42.195),
+                time: Time::new::<hour>(4.0) + Time::new::<minute>(30.0),
+            },
+        },
+    ]
+}
+
+/// Below this position, only "among the first ..." is reported instead of a range.
+const TOP_POSITIONS_THRESHOLD: usize = 5;
+
+/// The spread of finish times in a race field, as standard deviation of their logarithm.
+const FINISH_TIME_LOG_SPREAD: f64 = 0.22;
+/// How far off the typical median finish times may be for a particular race.
+const FINISH_TIME_MEDIAN_UNCERTAINTY: f64 = 0.05;
+
+/// The error function, approximated as in Abramowitz and Stegun, formula 7.1.26.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let value = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 {
+        -value
+    } else {
+        value
+    }
+}
+
+/// The fraction of a race field that finishes faster than the given time, assuming
+/// log-normally distributed finish times around the given median.
+fn fraction_faster(time: &Time, median: &Time) -> f64 {
+    let z = (*time / *median).get::<ratio>().ln() / FINISH_TIME_LOG_SPREAD;
+    0.5 * (1.0 + erf(z / std::f64::consts::SQRT_2))
+}
+
+fn display_ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+fn standard_distances(use_miles: bool) -> Vec<NamedLength> {
+    if use_miles {
+        vec![
+            NamedLength {
+                name: String::from("100 yd"),
+                distance: Length::new::<yard>(100.0),
+            },
+            NamedLength {
+                name: String::from("1/8 mi"),
+                distance: Length::new::<mile>(0.125),
+            },
+            NamedLength {
+                name: String::from("1/4 mi"),
+                distance: Length::new::<mile>(0.25),
+            },
+            NamedLength {
+                name: String::from("1 mi"),
+                distance: Length::new::<mile>(1.0),
+            },
+            NamedLength {
+                name: String::from("half marathon"),
+                distance: Length::new::<kilometer>(21.0975),
+            },
+            NamedLength {
+                name: String::from("marathon"),
+                distance: Length::new::<kilometer>(42.195),
+            },
+        ]
+    } else {
+        vec![
+            NamedLength {
+                name: String::from("100 m"),
+                distance: Length::new::<meter>(100.0),
+            },
+            NamedLength {
+                name: String::from("1 km"),
+                distance: Length::new::<kilometer>(1.0),
+            },
+            NamedLength {
+                name: String::from("5 km"),
+                distance: Length::new::<kilometer>(5.0),
+            },
+            NamedLength {
+                name: String::from("10 km"),
+                distance: Length::new::<kilometer>(10.0),
+            },
+            NamedLength {
+                name: String::from("half marathon"),
+                distance: Length::new::<kilometer>(21.0975),
+            },
+            NamedLength {
+                name: String::from("marathon"),
+                distance: Length::new::<kilometer>(42.195),
+            },
+        ]
+    }
+}
+
+fn other_performances() -> Vec<NamedVelocity> {
+    vec![
+        NamedVelocity {
+            name: String::from("Ashprihanal Aalto\'s 3100 mi (longest ultra marathon) WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(5.1480),
+            note: Some("run on a loop of half a mile around a block in New York"),
+        },
+        NamedVelocity {
+            name: String::from("Yohann Diniz\' 50 km race walk WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(14.1143),
+            note: Some("race walking, where running is not allowed"),
+        },
+        NamedVelocity {
+            name: String::from("Eliud Kipchoge\'s inofficial marathon WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(21.1563),
+            note: Some(
+                "with rotating pacers, a pace car and drinks handed from a bike, so not ratified",
+            ),
+        },
+        NamedVelocity {
+            name: String::from("Kenenisa Bekele\'s 10000 m WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(22.8205),
+            note: None,
+        },
+        NamedVelocity {
+            name: String::from("Usain Bolt\'s 100 m WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(37.5783),
+            note: Some("with a tailwind of 0.9 m/s"),
+        },
+        NamedVelocity {
+            name: String::from("Cheetah Sarah\'s 100 m animal WR"),
+            velocity: Velocity::new::<kilometer_per_hour>(60.5042),
+            note: Some("chasing a lure"),
+        },
+    ]
+}
+
+/// A pseudo-random number derived from the seed by the SplitMix64 generator, which is plenty for
+/// picking a fun fact.
+fn random_number(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn display_time(time: &Time) -> String {
+    // Splitting whole milliseconds instead of subtracting floats ensures that no part becomes
+    // negative and that the seconds cannot be rounded up to 60.000.
+    let milliseconds = (time.get::<second>() * 1000.0).round().max(0.0) as u64;
+
+    let d = milliseconds / 86_400_000;
+    let h = milliseconds / 3_600_000 % 24;
+    let m = milliseconds / 60_000 % 60;
+    let s = (milliseconds % 60_000) as f64 / 1000.0;
+
+    if d > 0 {
+        format!("{} d {} h {} min {:.3} s", d, h, m, s)
+    } else if h > 0 {
+        format!("{} h {} min {:.3} s", h, m, s)
+    } else {
+        if m > 0 {
+            format!("{} min {:.3} s", m, s)
+        } else {
+            format!("{:.3} s", s)
+        }
+    }
+}
+
+/// Displays a time like a stopwatch, e.g. "52:00" or "1:02:03", rounded to whole seconds.
+fn display_clock_time(time: &Time) -> String {
+    let seconds = time.get::<second>().round().max(0.0) as u64;
+
+    let h = seconds / 3600;
+    let m = seconds / 60 % 60;
+    let s = seconds % 60;
+
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// Rounds a time to the nearest multiple of the given number of seconds.
+fn round_time_to(time: &Time, seconds: f64) -> Time {
+    Time::new::<second>((time.get::<second>() / seconds).round() * seconds)
+}
+
+fn display_distance(distance: &Length, use_miles: bool) -> String {
+    if use_miles {
+        format!("{:.3} {}", distance.get::<mile>(), mile::abbreviation())
+    } else {
+        format!(
+            "{:.3} {}",
+            distance.get::<kilometer>(),
+            kilometer::abbreviation()
+        )
+    }
+}
+
+fn display_velocity(velocity: &Velocity, use_miles: bool) -> String {
+    if use_miles {
+        format!(
+            "{:.3} {}",
+            velocity.get::<mile_per_hour>(),
+            mile_per_hour::abbreviation()
+        )
+    } else {
+        format!(
+            "{:.3} {}",
+            velocity.get::<kilometer_per_hour>(),
+            kilometer_per_hour::abbreviation()
+        )
+    }
+}
+
+fn display_seconds(time: &Time) -> String {
+    format!("{:.3} {}", time.get::<second>(), second::abbreviation())
+}
+
+/// Prints how a value was derived, as requested by `--show-formula`.
+fn print_formula(formula: &str) {
+    report!("{}", format!("  {}", formula).dimmed());
+}
+
+/// The distance that pace is given for, one kilometer or one mile.
+fn unit_distance(use_miles: bool) -> Length {
+    if use_miles {
+        Length::new::<mile>(1.0)
+    } else {
+        Length::new::<kilometer>(1.0)
+    }
+}
+
+fn display_pace(velocity: &Velocity, use_miles: bool) -> String {
+    display_pace_per(
+        velocity,
+        &unit_distance(use_miles),
+        PaceFormat::MinutesSeconds,
+    )
+}
+
+/// Displays the time needed for the given distance at this velocity, like "1 min 36.000 s per
+/// 400 m" or, in decimal minutes, "1.60 min per 400 m".
+fn display_pace_per(velocity: &Velocity, pace_unit: &Length, pace_format: PaceFormat) -> String {
+    format!(
+        "{} per {}",
+        display_pace_time(&(*pace_unit / *velocity), pace_format),
+        display_pace_unit(pace_unit)
+    )
+}
+
+/// Displays the distance a pace is given for, like "km" or "400 m".
+fn display_pace_unit(pace_unit: &Length) -> String {
+    if is_same_distance(pace_unit, &unit_distance(false)) {
+        kilometer::abbreviation().to_string()
+    } else if is_same_distance(pace_unit, &unit_distance(true)) {
+        mile::abbreviation().to_string()
+    } else {
+        format!("{} {}", pace_unit.get::<meter>(), meter::abbreviation())
+    }
+}
+
+/// Displays the time of a pace (or a difference between paces) in the given format.
+fn display_pace_time(time: &Time, pace_format: PaceFormat) -> String {
+    match pace_format {
+        PaceFormat::MinutesSeconds => display_time(time),
+        PaceFormat::Tenths => {
+            // Rounding to whole tenths first makes e.g. 4:59.96 carry over to 5:00.0.
+            let tenths = (time.get::<second>() * 10.0).round() as u64;
+            format!(
+                "{}:{:02}.{} {}",
+                tenths / 600,
+                tenths % 600 / 10,
+                tenths % 10,
+                minute::abbreviation()
+            )
+        }
+        PaceFormat::DecimalMinutes => {
+            format!("{:.2} {}", time.get::<minute>(), minute::abbreviation())
+        }
+    }
+}
+
+/// Parses the distance to give the pace for, which may be just a unit like "km" or "mi".
+fn parse_pace_unit(input: &str) -> anyhow::Result<Length> {
+    let pace_unit = if input.trim().starts_with(char::is_alphabetic) {
+        parse_distance(&format!("1 {}", input.trim()))?
+    } else {
+        parse_distance(input)?
+    };
+    if pace_unit <= Length::new::<meter>(0.0) {
+        return Err(anyhow!(
+            "The distance to give the pace for must be longer than zero."
+        ));
+    }
+    Ok(pace_unit)
+}
+
+fn print_run(run: &Run, use_miles: bool, show_formula: bool) {
+    report!(
+        "Today, you ran {} in {}.",
+        display_distance(&run.distance, use_miles).emphasized(),
+        display_time(&run.time).emphasized()
+    );
+    report!(
+        "{}",
+        format!(
+            "Your average velocity was {}.",
+            display_velocity(&run.average_velocity(), use_miles)
+        )
+        .emphasized()
+    );
+    if show_formula {
+        print_formula(&format!(
+            "velocity = distance / time = {} / {} = {}",
+            display_distance(&run.distance, use_miles),
+            display_seconds(&run.time),
+            display_velocity(&run.average_velocity(), use_miles)
+        ));
+    }
+}
+
+/// Prints distance, velocity and pace side by side in metric and imperial units.
+fn print_mirror(run: &Run) {
+    let mut mirror_table = Table::new();
+    mirror_table.set_format(*format::consts::FORMAT_CLEAN);
+    mirror_table.set_titles(row!["", r -> "metric", r -> "imperial"]);
+    mirror_table.add_row(row![
+        "distance",
+        r -> display_distance(&run.distance, false),
+        r -> display_distance(&run.distance, true)
+    ]);
+    mirror_table.add_row(row![
+        "velocity",
+        r -> display_velocity(&run.average_velocity(), false),
+        r -> display_velocity(&run.average_velocity(), true)
+    ]);
+    mirror_table.add_row(row![
+        "pace",
+        r -> display_pace(&run.average_velocity(), false),
+        r -> display_pace(&run.average_velocity(), true)
+    ]);
+    print_table(&mirror_table);
+}
+
+/// Up to this temperature (in °C), the heat does not slow you down.
+const OPTIMAL_RACE_TEMPERATURE: f64 = 10.0;
+/// How much longer a race takes per degree Celsius above the optimal temperature.
+const HEAT_SLOWDOWN_PER_DEGREE: f64 = 0.003;
+
+/// The factor by which a race at the given temperature (in °C) takes longer than in ideal
+/// conditions.
+fn heat_factor(temperature: f64) -> f64 {
+    1.0 + HEAT_SLOWDOWN_PER_DEGREE * (temperature - OPTIMAL_RACE_TEMPERATURE).max(0.0)
+}
+
+/// The heat index (in °C) for the given temperature (in °C) and relative humidity (in percent),
+/// following the formula of the US National Weather Service.
+fn heat_index(temperature: f64, humidity: f64) -> f64 {
+    let t = temperature * 9.0 / 5.0 + 32.0;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + humidity * 0.094);
+    let fahrenheit = if (simple + t) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut index = -42.379 + 2.049_015_23 * t + 10.143_331_27 * humidity
+            - 0.224_755_41 * t * humidity
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * humidity * humidity
+            + 0.001_228_74 * t * t * humidity
+            + 0.000_852_82 * t * humidity * humidity
+            - 0.000_001_99 * t * t * humidity * humidity;
+        if humidity < 13.0 && (80.0..=112.0).contains(&t) {
+            index -= (13.0 - humidity) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if humidity > 85.0 && (80.0..=87.0).contains(&t) {
+            index += (humidity - 85.0) / 10.0 * (87.0 - t) / 5.0;
+        }
+        index
+    };
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+/// Whether two distances are the same up to rounding, like a marathon given in miles.
+fn is_same_distance(a: &Length, b: &Length) -> bool {
+    (*a - *b).abs() < Length::new::<meter>(1.0)
+}
+
+/// Marks a text as good (green) or bad (red) news, or with "(+)" or "(-)" if colors are off.
+fn display_judgement(text: String, good: bool) -> String {
+    match (colored::control::SHOULD_COLORIZE.should_colorize(), good) {
+        (true, true) => text.green().to_string(),
+        (true, false) => text.red().to_string(),
+        (false, true) => format!("(+) {}", text),
+        (false, false) => format!("(-) {}", text),
+    }
+}
+
+/// Tells by how much a time is ahead of or behind the goal time.
+fn display_goal_delta(time: &Time, goal: &Time) -> String {
+    if time <= goal {
+        display_judgement(
+            format!("{} ahead of your goal", display_time(&(*goal - *time))),
+            true,
+        )
+    } else {
+        display_judgement(
+            format!("{} behind your goal", display_time(&(*time - *goal))),
+            false,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn print_predictions(
+    run: &Run,
+    use_miles: bool,
+    show_formula: bool,
+    model: PredictionModel,
+    goal: Option<&Run>,
+    relative: bool,
+    race_temperature: Option<f64>,
+    race_humidity: Option<f64>,
+) {
+    // With humidity, the heat index determines how much the heat slows you down.
+    let effective_temperature = race_temperature.map(|temperature| match race_humidity {
+        Some(humidity) => heat_index(temperature, humidity),
+        None => temperature,
+    });
+    let conditions = race_temperature.map(|temperature| match race_humidity {
+        Some(humidity) => format!("{} °C and {}% humidity", temperature, humidity),
+        None => format!("{} °C", temperature),
+    });
+    let mut distances = standard_distances(use_miles);
+    if let Some(goal) = goal {
+        if !distances
+            .iter()
+            .any(|distance| is_same_distance(&distance.distance, &goal.distance))
+        {
+            distances.push(NamedLength {
+                name: display_distance(&goal.distance, use_miles),
+                distance: goal.distance,
+            });
+            distances.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+        }
+    }
+
+    let mut dist_table = Table::new();
+    dist_table.set_format(*format::consts::FORMAT_CLEAN);
+    let mut previous: Option<(&NamedLength, Time)> = None;
+    for distance in &distances {
+        let time = run.predicted_time(&distance.distance, model);
+        let (name, prefix, shown) = match previous {
+            Some((previous_distance, previous_time)) if relative => (
+                format!("{} – {}", previous_distance.name, distance.name),
+                "+",
+                time - previous_time,
+            ),
+            _ => (distance.name.clone(), "", time),
+        };
+        let shown_time = format!("{}{}", prefix, display_time(&shown));
+        previous = Some((distance, time));
+        let mut dist_row = if show_formula {
+            row![
+                r -> name,
+                shown_time,
+                match model {
+                    PredictionModel::Linear => format!(
+                        "= {} / {} × {}",
+                        display_distance(&distance.distance, use_miles),
+                        display_distance(&run.distance, use_miles),
+                        display_seconds(&run.time)
+                    ),
+                    PredictionModel::Riegel => format!(
+                        "= {} × ({} / {})^{}",
+                        display_seconds(&run.time),
+                        display_distance(&distance.distance, use_miles),
+                        display_distance(&run.distance, use_miles),
+                        RIEGEL_EXPONENT
+                    ),
+                    PredictionModel::Cameron => format!(
+                        "= {} × {} / {} × a({}) / a({})",
+                        display_seconds(&run.time),
+                        display_distance(&distance.distance, use_miles),
+                        display_distance(&run.distance, use_miles),
+                        display_distance(&run.distance, use_miles),
+                        display_distance(&distance.distance, use_miles)
+                    ),
+                }
+                .dimmed()
+            ]
+        } else {
+            row![r -> name, shown_time]
+        };
+        if let (Some(temperature), Some(conditions)) = (effective_temperature, &conditions) {
+            dist_row.insert_cell(
+                2,
+                Cell::new(&format!(
+                    "{}{} at {}",
+                    prefix,
+                    display_time(&(shown * heat_factor(temperature))),
+                    conditions
+                )),
+            );
+        }
+        if let Some(goal) = goal.filter(|goal| is_same_distance(&distance.distance, &goal.distance))
+        {
+            dist_row.add_cell(Cell::new(&display_goal_delta(&time, &goal.time)));
+        }
+        dist_table.add_row(dist_row);
+    }
+    if show_formula {
+        match model {
+            PredictionModel::Linear => print_formula("time = other distance / distance × time"),
+            PredictionModel::Riegel => print_formula(&format!(
+                "time = time × (other distance / distance)^{}",
+                RIEGEL_EXPONENT
+            )),
+            PredictionModel::Cameron => {
+                print_formula(
+                    "time = time × other distance / distance × a(distance) / a(other distance)",
+                );
+                print_formula("a(d) = 13.49681 − 0.048865 × d + 2.438936 / d^0.7905, d in miles");
+            }
+        }
+        if relative {
+            print_formula("segment time = time − time for the previous distance");
+        }
+        if race_temperature.is_some() {
+            print_formula(&format!(
+                "time at race temperature = time × (1 + {} × (temperature − {} °C))",
+                HEAT_SLOWDOWN_PER_DEGREE, OPTIMAL_RACE_TEMPERATURE
+            ));
+            if race_humidity.is_some() {
+                print_formula("temperature = heat index of temperature and humidity");
+            }
+        }
+    }
+    print_table(&dist_table);
+    if let (Some(temperature), Some(_)) = (effective_temperature, race_humidity) {
+        report!(
+            "The heat index is {:.1} °C, so your pace would be {}.",
+            temperature,
+            display_pace(
+                &(run.average_velocity() / heat_factor(temperature)),
+                use_miles
+            )
+        );
+    }
+}
+
+/// Races taking longer than this many minutes need carbohydrate intake during the race.
+const CARB_INTAKE_MIN_DURATION: f64 = 90.0;
+
+/// Prints how many carbohydrates to take during the races that are long enough to need them.
+fn print_carb_needs(run: &Run, use_miles: bool, model: PredictionModel, carbs_per_hour: f64) {
+    for distance in &standard_distances(use_miles) {
+        let time = run.predicted_time(&distance.distance, model);
+        if time.get::<minute>() > CARB_INTAKE_MIN_DURATION {
+            report!(
+                "For a {} in {}, plan on taking {} of carbohydrates ({} g per hour).",
+                distance.name,
+                display_time(&time),
+                format!("{:.0} g", time.get::<hour>() * carbs_per_hour).emphasized(),
+                carbs_per_hour
+            );
+        }
+    }
+}
+
+/// A playful rating of a run, awarded from a minimum VDOT on.
+struct Tier {
+    name: &'static str,
+    min_vdot: f64,
+    color: Color,
+}
+
+/// The tiers from the highest to the lowest. A VDOT of 30 is a 5 km in about 30:40 min, 40 in
+/// 24:08 min, 50 in 19:57 min and 60 in 16:39 min.
+fn tiers() -> Vec<Tier> {
+    vec![
+        Tier {
+            name: "platinum",
+            min_vdot: 60.0,
+            color: Color::BrightCyan,
+        },
+        Tier {
+            name: "gold",
+            min_vdot: 50.0,
+            color: Color::Yellow,
+        },
+        Tier {
+            name: "silver",
+            min_vdot: 40.0,
+            color: Color::White,
+        },
+        Tier {
+            name: "bronze",
+            min_vdot: 30.0,
+            color: Color::Red,
+        },
+    ]
+}
+
+/// The maximum number of bins in the histogram of split times.
+const SPLIT_HISTOGRAM_BINS: usize = 5;
+/// The length of the longest bar in the histogram of split times.
+const SPLIT_HISTOGRAM_WIDTH: usize = 30;
+
+/// Colors a text on a gradient from green (for 0, the fastest) over yellow to red (for 1, the
+/// slowest).
+fn display_gradient(text: &str, slowness: f64) -> String {
+    let gradient = [
+        Color::Green,
+        Color::BrightGreen,
+        Color::Yellow,
+        Color::BrightRed,
+        Color::Red,
+    ];
+    let index = ((slowness * (gradient.len() - 1) as f64).round().max(0.0) as usize)
+        .min(gradient.len() - 1);
+    text.color(gradient[index]).to_string()
+}
+
+/// Prints a histogram of the split times and their standard deviation.
+fn print_splits(splits: &[Time]) {
+    let seconds = splits
+        .iter()
+        .map(|split| split.get::<second>())
+        .collect::<Vec<_>>();
+    let fastest = seconds.iter().cloned().fold(f64::INFINITY, f64::min);
+    let slowest = seconds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let bins = if slowest > fastest {
+        SPLIT_HISTOGRAM_BINS.min(seconds.len())
+    } else {
+        1
+    };
+    let bin_width = (slowest - fastest) / bins as f64;
+
+    let mut counts = vec![0; bins];
+    for split in &seconds {
+        let bin = if bin_width > 0.0 {
+            (((split - fastest) / bin_width) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        counts[bin] += 1;
+    }
+    let max_count = *counts.iter().max().expect("there are no bins!");
+
+    report!(
+        "{}",
+        "\nThis is how your split times are distributed:".emphasized()
+    );
+    let mut split_table = Table::new();
+    split_table.set_format(*format::consts::FORMAT_CLEAN);
+    for (bin, count) in counts.iter().enumerate() {
+        let from = Time::new::<second>(fastest + bin as f64 * bin_width);
+        let to = Time::new::<second>(fastest + (bin + 1) as f64 * bin_width);
+        split_table.add_row(row![
+            r -> display_time(&from),
+            "–",
+            r -> display_time(&to),
+            format!(
+                "{} {}",
+                display_gradient(
+                    &"#".repeat(count * SPLIT_HISTOGRAM_WIDTH / max_count),
+                    if bins > 1 {
+                        bin as f64 / (bins - 1) as f64
+                    } else {
+                        0.0
+                    }
+                ),
+                count
+            )
+        ]);
+    }
+    print_table(&split_table);
+
+    let mean = seconds.iter().sum::<f64>() / seconds.len() as f64;
+    let deviation = (seconds
+        .iter()
+        .map(|split| (split - mean).powi(2))
+        .sum::<f64>()
+        / seconds.len() as f64)
+        .sqrt();
+    report!(
+        "The standard deviation of your splits is {} ({:.1}% of the average split).",
+        display_time(&Time::new::<second>(deviation)).emphasized(),
+        deviation / mean * 100.0
+    );
+}
+
+/// Lists the splits with the fastest and the slowest one marked, and tells how far apart they
+/// are.
+fn print_best_split(splits: &[Time]) {
+    let extreme = |is_better: fn(&Time, &Time) -> bool| {
+        (0..splits.len()).fold(0, |best, index| {
+            if is_better(&splits[index], &splits[best]) {
+                index
+            } else {
+                best
+            }
+        })
+    };
+    let fastest = extreme(|a, b| a < b);
+    let slowest = extreme(|a, b| a > b);
+
+    report!("{}", "\nYour splits:".emphasized());
+    let mut split_table = Table::new();
+    split_table.set_format(*format::consts::FORMAT_CLEAN);
+    for (index, split) in splits.iter().enumerate() {
+        let mark = if splits.len() < 2 {
+            String::new()
+        } else if index == fastest {
+            display_judgement(String::from("fastest"), true)
+        } else if index == slowest {
+            display_judgement(String::from("slowest"), false)
+        } else {
+            String::new()
+        };
+        split_table.add_row(row![
+            r -> format!("split {}", index + 1),
+            r -> display_time(split),
+            mark
+        ]);
+    }
+    print_table(&split_table);
+
+    let spread = splits[slowest] - splits[fastest];
+    report!(
+        "Your fastest split was split {} in {} and your slowest split {} in {}, a spread of {} ({:.1}%).",
+        fastest + 1,
+        display_time(&splits[fastest]),
+        slowest + 1,
+        display_time(&splits[slowest]),
+        display_time(&spread).emphasized(),
+        (spread / splits[fastest]).get::<percent>()
+    );
+}
+
+/// Up to this many percent of difference between the halves, pacing counts as even.
+const EVEN_PACING_TOLERANCE: f64 = 1.0;
+
+/// Prints how much slower the second half of the splits was than the first half.
+fn print_fade_analysis(splits: &[Time]) {
+    let half = splits.len() / 2;
+    let total = |splits: &[Time]| {
+        splits
+            .iter()
+            .fold(Time::new::<second>(0.0), |sum, split| sum + *split)
+    };
+    let mut first_half = total(&splits[..half]);
+    let mut second_half = total(&splits[splits.len() - half..]);
+    // With an odd number of splits, the one in the middle belongs to both halves equally.
+    if splits.len() % 2 == 1 {
+        first_half += splits[half] / 2.0;
+        second_half += splits[half] / 2.0;
+    }
+    let fade = ((second_half / first_half).get::<ratio>() - 1.0) * 100.0;
+    let verdict = if fade.abs() <= EVEN_PACING_TOLERANCE {
+        "even pacing"
+    } else if fade > 0.0 {
+        "a positive split"
+    } else {
+        "a negative split"
+    };
+    report!(
+        "Your second half ({}) was {:.1}% {} than your first half ({}), that is {}.",
+        display_time(&second_half),
+        fade.abs(),
+        if fade > 0.0 { "slower" } else { "faster" },
+        display_time(&first_half),
+        verdict.emphasized()
+    );
+}
+
+/// Prints how much faster you would need to get per day to reach your goal in time, with the
+/// time for the goal distance predicted by the model.
+fn print_required_improvement(run: &Run, goal: &Run, days: Option<f64>, model: PredictionModel) {
+    let days = match days {
+        Some(days) => days,
+        None => return,
+    };
+    let time = run.predicted_time(&goal.distance, model);
+    if time > goal.time {
+        report!(
+            "To reach your goal in {} days, you need to get {} faster per day.",
+            days,
+            display_time(&((time - goal.time) / days)).emphasized()
+        );
+    }
+}
+
+/// The velocity given by `--compare-velocity`, if there is one.
+fn custom_performance(options: &RunOptions) -> anyhow::Result<Option<NamedVelocity>> {
+    match &options.compare_velocity {
+        Some(velocity) => Ok(Some(NamedVelocity {
+            name: String::from("custom"),
+            velocity: parse_velocity(velocity)
+                .with_context(|| "Could not understand the velocity to compare with.")?,
+            note: None,
+        })),
+        None => Ok(None),
+    }
+}
+
+/// The other performances to compare with, as selected by `--compare-within` and
+/// `--compare-closest`, ordered by velocity.
+fn selected_performances(run: &Run, options: &RunOptions) -> anyhow::Result<Vec<NamedVelocity>> {
+    let mut velocities = other_performances();
+    if let Some(within) = options.compare_within {
+        velocities.retain(|velocity| {
+            (velocity.velocity / run.average_velocity() - Ratio::new::<ratio>(1.0))
+                .abs()
+                .get::<percent>()
+                <= within
+        });
+    }
+    if let Some(count) = options.compare_closest {
+        let deviation = |velocity: &NamedVelocity| {
+            (run.average_velocity() / velocity.velocity - Ratio::new::<ratio>(1.0)).abs()
+        };
+        velocities.sort_by(|a, b| deviation(a).partial_cmp(&deviation(b)).unwrap());
+        velocities.truncate(count);
+    }
+    // The custom velocity was asked for explicitly, so it is not subject to the selection.
+    velocities.extend(custom_performance(options)?);
+    velocities.sort_by(|a, b| a.velocity.partial_cmp(&b.velocity).unwrap());
+    Ok(velocities)
+}
+
+fn comparison_ratio(run: &Run, velocity: &NamedVelocity, basis: CompareBasis) -> f64 {
+    match basis {
+        CompareBasis::Speed => (run.average_velocity() / velocity.velocity).get::<ratio>(),
+        CompareBasis::Pace | CompareBasis::RecordPace => {
+            (velocity.velocity / run.average_velocity()).get::<ratio>()
+        }
+    }
+}
+
+/// Displays by how much slower or faster per kilometer (or mile) the run was than the other
+/// performance.
+fn display_pace_difference(
+    run: &Run,
+    velocity: &NamedVelocity,
+    pace_unit: &Length,
+    pace_format: PaceFormat,
+) -> String {
+    let difference = *pace_unit / run.average_velocity() - *pace_unit / velocity.velocity;
+    if difference >= Time::new::<second>(0.0) {
+        format!(
+            "{} slower per {}",
+            display_pace_time(&difference, pace_format),
+            display_pace_unit(pace_unit)
+        )
+    } else {
+        format!(
+            "{} faster per {}",
+            display_pace_time(&-difference, pace_format),
+            display_pace_unit(pace_unit)
+        )
+    }
+}
+
+/// The JSON schema of the comparison written by `--compare-out`.
+const COMPARISON_SCHEMA: &str = r#"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "title": "Comparison with other performances",
+  "type": "array",
+  "items": {
+    "type": "object",
+    "properties": {
+      "name": { "type": "string", "description": "what the performance is" },
+      "velocity": { "type": "number", "description": "the average velocity of the performance" },
+      "unit": { "type": "string", "enum": ["km/h", "mi/h"], "description": "the unit of the velocity" },
+      "ratio": { "type": "number", "description": "your speed (or pace) divided by the one of the performance" }
+    },
+    "required": ["name", "velocity", "unit", "ratio"],
+    "additionalProperties": false
+  }
+}"#;
+
+/// A performance in the comparison written by `--compare-out`.
+#[derive(Serialize)]
+struct ComparisonRow<'a> {
+    name: &'a str,
+    velocity: f64,
+    unit: &'a str,
+    ratio: f64,
+}
+
+/// Writes the comparison with other performances to a JSON or CSV file.
+fn write_comparisons(
+    path: &str,
+    run: &Run,
+    velocities: &[NamedVelocity],
+    basis: CompareBasis,
+    use_miles: bool,
+) -> anyhow::Result<()> {
+    let (velocity_of, unit) = if use_miles {
+        (
+            Velocity::get::<mile_per_hour> as fn(&Velocity) -> f64,
+            mile_per_hour::abbreviation(),
+        )
+    } else {
+        (
+            Velocity::get::<kilometer_per_hour> as fn(&Velocity) -> f64,
+            kilometer_per_hour::abbreviation(),
+        )
+    };
+    let rows = velocities
+        .iter()
+        .map(|velocity| ComparisonRow {
+            name: &velocity.name,
+            velocity: velocity_of(&velocity.velocity),
+            unit,
+            ratio: comparison_ratio(run, velocity, basis),
+        })
+        .collect::<Vec<_>>();
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_ref().map(|extension| &extension[..]) {
+        Some("json") => {
+            let mut file = std::fs::File::create(path)?;
+            serde_json::to_writer_pretty(&mut file, &rows)?;
+            std::io::Write::write_all(&mut file, b"\n")?;
+        }
+        Some("csv") => {
+            let mut writer = csv::Writer::from_path(path)?;
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        _ => {
+            return Err(anyhow!(
+                "The file name must end in .json or .csv to tell the format."
+            ))
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn print_comparisons(
+    run: &Run,
+    velocities: &[NamedVelocity],
+    basis: CompareBasis,
+    use_miles: bool,
+    pace_unit: &Length,
+    pace_format: PaceFormat,
+    show_formula: bool,
+    gender_gap: Option<f64>,
+    age_factor: Option<f64>,
+    show_notes: bool,
+) {
+    let mut vel_table = Table::new();
+    vel_table.set_format(*format::consts::FORMAT_CLEAN);
+    for velocity in velocities {
+        let times_ratio = comparison_ratio(run, velocity, basis);
+        let times = match basis {
+            CompareBasis::RecordPace => {
+                display_pace_difference(run, velocity, pace_unit, pace_format)
+            }
+            _ => format!("{:.3} times", times_ratio),
+        };
+        if show_formula {
+            let substituted = match basis {
+                CompareBasis::Speed => format!(
+                    "= {} / {}",
+                    display_velocity(&run.average_velocity(), use_miles),
+                    display_velocity(&velocity.velocity, use_miles)
+                ),
+                CompareBasis::Pace => format!(
+                    "= {} / {}",
+                    display_pace(&run.average_velocity(), use_miles),
+                    display_pace(&velocity.velocity, use_miles)
+                ),
+                CompareBasis::RecordPace => format!(
+                    "= {} − {}",
+                    display_pace(&run.average_velocity(), use_miles),
+                    display_pace(&velocity.velocity, use_miles)
+                ),
+            };
+            vel_table.add_row(row![r -> times, velocity.name, substituted.dimmed()]);
+        } else {
+            vel_table.add_row(row![r -> times, velocity.name]);
+        }
+        if show_notes {
+            vel_table
+                .get_mut_row(vel_table.len() - 1)
+                .expect("the row was just added!")
+                .add_cell(Cell::new(velocity.note.unwrap_or("")));
+        }
+        if let Some(gap) = gender_gap {
+            let adjusted = match basis {
+                CompareBasis::Speed => times_ratio * (1.0 + gap),
+                CompareBasis::Pace | CompareBasis::RecordPace => times_ratio / (1.0 + gap),
+            };
+            vel_table
+                .get_mut_row(vel_table.len() - 1)
+                .expect("the row was just added!")
+                .insert_cell(
+                    1,
+                    Cell::new_align(
+                        &format!("({:.3} adjusted)", adjusted),
+                        format::Alignment::RIGHT,
+                    ),
+                );
+        }
+        if let Some(factor) = age_factor {
+            let age_graded = match basis {
+                CompareBasis::Speed => times_ratio / factor,
+                CompareBasis::Pace | CompareBasis::RecordPace => times_ratio * factor,
+            };
+            vel_table
+                .get_mut_row(vel_table.len() - 1)
+                .expect("the row was just added!")
+                .insert_cell(
+                    if gender_gap.is_some() { 2 } else { 1 },
+                    Cell::new_align(
+                        &format!("({:.3} age-graded)", age_graded),
+                        format::Alignment::RIGHT,
+                    ),
+                );
+        }
+    }
+
+    match basis {
+        CompareBasis::Speed => report!(
+            "{}",
+            "\nYour average velocity compared to those of other performances:".emphasized()
+        ),
+        CompareBasis::Pace => report!(
+            "{}",
+            "\nYour pace compared to those of other performances:".emphasized()
+        ),
+        CompareBasis::RecordPace => report!(
+            "{}",
+            "\nYour pace compared to the ones sustained in other performances:".emphasized()
+        ),
+    }
+    if show_formula {
+        match basis {
+            CompareBasis::Speed => print_formula("ratio = velocity / other velocity"),
+            CompareBasis::Pace => print_formula("ratio = pace / other pace"),
+            CompareBasis::RecordPace => print_formula("difference = pace − other pace"),
+        }
+        if let Some(gap) = gender_gap {
+            match basis {
+                CompareBasis::Speed => print_formula(&format!(
+                    "adjusted ratio = ratio × (1 + gender gap) = ratio × {:.3}",
+                    1.0 + gap
+                )),
+                CompareBasis::Pace | CompareBasis::RecordPace => print_formula(&format!(
+                    "adjusted ratio = ratio / (1 + gender gap) = ratio / {:.3}",
+                    1.0 + gap
+                )),
+            }
+        }
+        if let Some(factor) = age_factor {
+            match basis {
+                CompareBasis::Speed => print_formula(&format!(
+                    "age-graded ratio = ratio / age factor = ratio / {:.3}",
+                    factor
+                )),
+                CompareBasis::Pace | CompareBasis::RecordPace => print_formula(&format!(
+                    "age-graded ratio = ratio × age factor = ratio × {:.3}",
+                    factor
+                )),
+            }
+        }
+    }
+    print_table(&vel_table);
+}
+
+/// Compares the run with each world record at the same distance, by first estimating the time
+/// for the distance of the record with Riegel's formula.
+fn print_equivalent_comparisons(
+    run: &Run,
+    basis: CompareBasis,
+    use_miles: bool,
+    show_formula: bool,
+) {
+    let mut equivalent_table = Table::new();
+    equivalent_table.set_format(*format::consts::FORMAT_CLEAN);
+    for record in world_records(use_miles) {
+        let time = run.predicted_time(&record.run.distance, PredictionModel::Riegel);
+        let times_ratio = match basis {
+            CompareBasis::Speed => (record.run.time / time).get::<ratio>(),
+            CompareBasis::Pace | CompareBasis::RecordPace => {
+                (time / record.run.time).get::<ratio>()
+            }
+        };
+        equivalent_table.add_row(row![
+            r -> format!("{:.3} times", times_ratio),
+            format!("{} WR", record.name),
+            format!("(your equivalent time: {})", display_time(&time))
+        ]);
+    }
+
+    report!(
+        "{}",
+        "\nYour performance compared to the world records at their distances:".emphasized()
+    );
+    if show_formula {
+        print_formula(&format!(
+            "equivalent time = time × (record distance / distance)^{}",
+            RIEGEL_EXPONENT
+        ));
+        match basis {
+            CompareBasis::Speed => print_formula("ratio = record time / equivalent time"),
+            CompareBasis::Pace | CompareBasis::RecordPace => {
+                print_formula("ratio = equivalent time / record time")
+            }
+        }
+    }
+    print_table(&equivalent_table);
+}
+
+/// Prints how the distance and time were understood, to catch misparsed input early.
+fn print_explanation(run: &Run, options: &RunOptions) {
+    let distance = display_distance(&run.distance, options.use_miles);
+    let time = display_time(&run.time);
+    let explanation = match (
+        &options.run.fit,
+        &options.run.gpx,
+        &options.run.time,
+        &options.run.pace,
+    ) {
+        (Some(path), _, _, _) => format!(
+            "Parsed distance: {}; parsed time: {} (both from the FIT file '{}').",
+            distance, time, path
+        ),
+        (None, Some(path), _, _) => format!(
+            "Parsed distance: {}; parsed time: {} (the length and elapsed time of the track in the GPX file '{}').",
+            distance, time, path
+        ),
+        (None, None, Some(time_input), _) => format!(
+            "Parsed distance: {} (from '{}'); parsed time: {} (from '{}').",
+            distance,
+            options.run.distance.as_ref().expect("no distance given!"),
+            time,
+            time_input
+        ),
+        (None, None, None, Some(pace)) => format!(
+            "Parsed distance: {}; parsed time: {} (from '{}' and the pace '{}', which is {}).",
+            distance,
+            time,
+            options.run.distance.as_ref().expect("no distance given!"),
+            pace,
+            display_pace(&run.average_velocity(), options.use_miles)
+        ),
+        (None, None, None, None) => unreachable!("neither time nor pace given!"),
+    };
+    report!("{}", explanation.dimmed());
+}
+
+/// The ascent that vertical pace is given for, in meters (or feet with `--miles`).
+const VERTICAL_PACE_ASCENT: f64 = 100.0;
+
+/// Prints how fast the elevation gain was climbed, as mountain runners measure it.
+fn print_vertical(run: &Run, elevation: &Length, options: &RunOptions) -> anyhow::Result<()> {
+    let elevation = *elevation;
+    if elevation <= Length::new::<meter>(0.0) {
+        return Err(anyhow!(
+            "The vertical mode needs a positive elevation gain to climb."
+        ));
+    }
+    let (ascent, unit, pace_ascent) = if options.use_miles {
+        (
+            elevation.get::<foot>(),
+            foot::abbreviation(),
+            Length::new::<foot>(VERTICAL_PACE_ASCENT),
+        )
+    } else {
+        (
+            elevation.get::<meter>(),
+            meter::abbreviation(),
+            Length::new::<meter>(VERTICAL_PACE_ASCENT),
+        )
+    };
+    report!(
+        "You climbed {} at a vertical speed of {}.",
+        format!("{:.0} {}", ascent, unit).emphasized(),
+        format!("{:.0} {}/h", ascent / run.time.get::<hour>(), unit).emphasized()
+    );
+    report!(
+        "That is a vertical pace of {} per {} {} of ascent, or {} for a vertical kilometer.",
+        display_time(&(run.time * (pace_ascent / elevation).get::<ratio>())).emphasized(),
+        VERTICAL_PACE_ASCENT,
+        unit,
+        display_time(&(run.time * (Length::new::<kilometer>(1.0) / elevation).get::<ratio>()))
+    );
+    if options.show_formula {
+        print_formula("vertical speed = elevation gain / time");
+    }
+    Ok(())
+}
+
+/// Up to this altitude (in m), the thinner air does not slow you down.
+const ALTITUDE_THRESHOLD: f64 = 500.0;
+/// How much longer a run takes per kilometer of altitude above the threshold.
+const ALTITUDE_SLOWDOWN_PER_KILOMETER: f64 = 0.02;
+/// The part of the effort of running that goes into overcoming air resistance without wind.
+const AIR_RESISTANCE_SHARE: f64 = 0.04;
+/// How much of the effort a tailwind saves compared to what a headwind of the same velocity
+/// costs.
+const TAILWIND_BENEFIT: f64 = 0.5;
+
+/// The factor by which a run at the given altitude takes longer than at sea level.
+fn altitude_factor(altitude: &Length) -> f64 {
+    1.0 + ALTITUDE_SLOWDOWN_PER_KILOMETER
+        * ((altitude.get::<meter>() - ALTITUDE_THRESHOLD) / 1000.0).max(0.0)
+}
+
+/// The factor by which a run takes longer against the given headwind (negative for a
+/// tailwind), as the air resistance grows with the square of the velocity relative to the air.
+fn wind_factor(velocity: &Velocity, headwind: &Velocity) -> f64 {
+    let relative = ((*velocity + *headwind) / *velocity).get::<ratio>();
+    let change = AIR_RESISTANCE_SHARE * (relative * relative.abs() - 1.0);
+    if change >= 0.0 {
+        1.0 + change
+    } else {
+        1.0 + TAILWIND_BENEFIT * change
+    }
+}
+
+/// Parses a velocity that may be preceded by a sign, like a tailwind of "-10km/h".
+fn parse_signed_velocity(input: &str) -> anyhow::Result<Velocity> {
+    let input = input.trim();
+    match input.strip_prefix('-') {
+        Some(magnitude) => Ok(-parse_velocity(magnitude)?),
+        None => parse_velocity(input.trim_start_matches('+')),
+    }
+}
+
+/// Prints the time the run would have taken in standard conditions, i.e. at 10 °C on flat
+/// ground at sea level without wind, and in verbose mode how much each condition contributed.
+fn print_standardized(run: &Run, options: &RunOptions, inputs: &RunInputs) -> anyhow::Result<()> {
+    let mut corrections = Vec::new();
+    if let Some(temperature) = options.temp {
+        let (name, effective) = match options.humidity {
+            Some(humidity) => (
+                format!("{} °C and {}% humidity", temperature, humidity),
+                heat_index(temperature, humidity),
+            ),
+            None => (format!("{} °C", temperature), temperature),
+        };
+        corrections.push((name, heat_factor(effective)));
+    }
+    if let Some(altitude) = &options.altitude {
+        let altitude =
+            parse_distance(altitude).with_context(|| "Could not understand the altitude.")?;
+        corrections.push((
+            format!(
+                "an altitude of {}",
+                display_distance(&altitude, options.use_miles)
+            ),
+            altitude_factor(&altitude),
+        ));
+    }
+    if let Some(wind) = &options.wind {
+        let wind = parse_signed_velocity(wind).with_context(|| "Could not understand the wind.")?;
+        corrections.push((
+            if wind >= Velocity::new::<meter_per_second>(0.0) {
+                format!(
+                    "a headwind of {}",
+                    display_velocity(&wind, options.use_miles)
+                )
+            } else {
+                format!(
+                    "a tailwind of {}",
+                    display_velocity(&-wind, options.use_miles)
+                )
+            },
+            wind_factor(&run.average_velocity(), &wind),
+        ));
+    }
+    if let Some(elevation) = &inputs.elevation {
+        corrections.push((
+            format!(
+                "an elevation change of {}",
+                display_distance(elevation, options.use_miles)
+            ),
+            run.grade_effort(elevation),
+        ));
+    }
+    if corrections.is_empty() {
+        return Err(anyhow!(
+            "To standardize your run, give at least one of --temp, --altitude, --wind and --elevation."
+        ));
+    }
+
+    let factor = corrections
+        .iter()
+        .map(|(_, factor)| factor)
+        .product::<f64>();
+    let standard = Run {
+        distance: run.distance,
+        time: run.time / factor,
+    };
+    if options.verbose {
+        report!(
+            "{}",
+            "\nThis is how the conditions affected your time:".emphasized()
+        );
+        let mut correction_table = Table::new();
+        correction_table.set_format(*format::consts::FORMAT_CLEAN);
+        for (name, factor) in &corrections {
+            let change = run.time - run.time / *factor;
+            correction_table.add_row(row![
+                name,
+                r -> if change >= Time::new::<second>(0.0) {
+                    format!("+{}", display_time(&change))
+                } else {
+                    format!("−{}", display_time(&-change))
+                }
+            ]);
+        }
+        print_table(&correction_table);
+        if options.show_formula {
+            print_formula("standard time = time / (heat factor × altitude factor × wind factor × grade effort)");
+        }
+    }
+    report!(
+        "In standard conditions, you would have needed {}, at a pace of {}.",
+        display_time(&standard.time).emphasized(),
+        display_pace(&standard.average_velocity(), options.use_miles)
+    );
+    Ok(())
+}
+
+/// The options of `tir run` that have to be parsed, parsed and checked against the run once
+/// before anything is printed or saved, so that invalid input fails without a partial report.
+struct RunInputs {
+    pace_unit: Length,
+    goal: Option<Run>,
+    splits: Vec<Time>,
+    /// The run without the warmup and cooldown, if any were given.
+    main_set: Option<Run>,
+    elevation: Option<Length>,
+    threshold: Option<Velocity>,
+    elapsed: Option<Time>,
+    weight: Option<Mass>,
+}
+
+impl RunInputs {
+    fn parse(options: &RunOptions, run: &Run) -> anyhow::Result<Self> {
+        let segments = options
+            .warmup
+            .iter()
+            .map(|warmup| parse_segment(warmup).with_context(|| "Could not understand the warmup."))
+            .chain(options.cooldown.iter().map(|cooldown| {
+                parse_segment(cooldown).with_context(|| "Could not understand the cooldown.")
+            }))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let elapsed = options
+            .elapsed
+            .as_ref()
+            .map(|elapsed| parse_time(elapsed))
+            .transpose()
+            .with_context(|| "Could not understand the elapsed time.")?;
+        if let Some(elapsed) = elapsed {
+            if elapsed < run.time {
+                return Err(anyhow!(
+                    "The elapsed time must not be shorter than the moving time."
+                ));
+            }
+        }
+        Ok(RunInputs {
+            pace_unit: match &options.pace_per {
+                Some(pace_per) => parse_pace_unit(pace_per)
+                    .with_context(|| "Could not understand the distance to give the pace for.")?,
+                None => unit_distance(options.use_miles),
+            },
+            goal: parse_goal(&options.goal).with_context(|| "Could not understand the goal.")?,
+            splits: options
+                .splits
+                .iter()
+                .map(|split| parse_time(split).with_context(|| "Could not understand the splits."))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            main_set: if segments.is_empty() {
+                None
+            } else {
+                Some(run.main_set(&segments)?)
+            },
+            elevation: options
+                .elevation
+                .as_ref()
+                .map(|elevation| parse_signed_distance(elevation))
+                .transpose()
+                .with_context(|| "Could not understand the elevation change.")?,
+            threshold: options
+                .threshold
+                .as_ref()
+                .map(|threshold| parse_pace(threshold, options.input_in_miles()))
+                .transpose()
+                .with_context(|| "Could not understand the threshold pace.")?,
+            elapsed,
+            weight: options
+                .weight
+                .as_ref()
+                .map(|weight| parse_mass(weight))
+                .transpose()
+                .with_context(|| "Could not understand the weight.")?,
+        })
+    }
+
+    /// The elevation change, or none for a run on flat ground.
+    fn elevation_or_flat(&self) -> Length {
+        self.elevation.unwrap_or_else(|| Length::new::<meter>(0.0))
+    }
+}
+
+/// Prints what was read from an activity file besides the distance and time.
+fn print_activity(activity: &Activity, use_miles: bool) {
+    match activity {
+        Activity::Fit(path, activity) => {
+            report!("Read {} records from \"{}\".", activity.records, path);
+            if let Some(heart_rate) = activity.average_heart_rate {
+                report!("Your average heart rate was {} bpm.", heart_rate);
+            }
+            if let Some(cadence) = activity.average_cadence {
+                report!(
+                    "Your average cadence was {} steps per minute.",
+                    2 * u32::from(cadence)
+                );
+            }
+        }
+        Activity::Gpx(path, activity) => {
+            report!("Read {} track points from \"{}\".", activity.points, path);
+            if let Some(time) = activity.grade_adjusted_time {
+                let flat = Run {
+                    distance: activity.run.distance,
+                    time,
+                };
+                report!(
+                    "Adjusted for the grade of each segment, your pace was {}, like running {} on flat ground.",
+                    display_pace(&flat.average_velocity(), use_miles),
+                    display_time(&time)
+                );
+            }
+        }
+    }
+}
+
+/// Prints the run as one line to share, e.g. in a chat.
+fn print_share(run: &Run, options: &RunOptions, inputs: &RunInputs) {
+    report!("{}", share_line(run, options, inputs));
+}
+
+/// The line to share for a run, like "🏃 Ran 5.0 km in 26:00 — 5:12/km pace!".
+fn share_line(run: &Run, options: &RunOptions, inputs: &RunInputs) -> String {
+    let (distance, unit) = if options.use_miles {
+        (run.distance.get::<mile>(), mile::abbreviation())
+    } else {
+        (run.distance.get::<kilometer>(), kilometer::abbreviation())
+    };
+    let pace = match options.pace_format {
+        PaceFormat::DecimalMinutes => display_pace_per(
+            &run.average_velocity(),
+            &inputs.pace_unit,
+            options.pace_format,
+        ),
+        _ => format!(
+            "{}/{}",
+            display_clock_time(&(inputs.pace_unit / run.average_velocity())),
+            display_pace_unit(&inputs.pace_unit)
+        ),
+    };
+    format!(
+        "{}Ran {:.1} {} in {} — {} pace!",
+        if options.no_emoji { "" } else { "🏃 " },
+        distance,
+        unit,
+        display_clock_time(&run.time),
+        pace
+    )
+}
+
+/// Prints the run as a card like the one Strava shows for an activity.
+fn print_strava_card(run: &Run, options: &RunOptions, inputs: &RunInputs) {
+    let (distance, unit, elevation_unit) = if options.use_miles {
+        (
+            run.distance.get::<mile>(),
+            mile::abbreviation(),
+            foot::abbreviation(),
+        )
+    } else {
+        (
+            run.distance.get::<kilometer>(),
+            kilometer::abbreviation(),
+            meter::abbreviation(),
+        )
+    };
+    let mut entries = vec![
+        ("Distance", format!("{:.2} {}", distance, unit)),
+        ("Moving Time", display_clock_time(&run.time)),
+        (
+            "Pace",
+            display_pace_per(
+                &run.average_velocity(),
+                &inputs.pace_unit,
+                options.pace_format,
+            ),
+        ),
+    ];
+    if let Some(elevation) = &inputs.elevation {
+        let elevation = if options.use_miles {
+            elevation.get::<foot>()
+        } else {
+            elevation.get::<meter>()
+        };
+        entries.push(("Elevation", format!("{:.0} {}", elevation, elevation_unit)));
+    }
+    if let Some(elapsed) = &inputs.elapsed {
+        entries.push(("Elapsed Time", display_clock_time(elapsed)));
+    }
+
+    let mut card = Table::new();
+    card.set_format(*format::consts::FORMAT_CLEAN);
+    card.add_row(
+        entries
+            .iter()
+            .map(|(_, value)| Cell::new(&value.emphasized()))
+            .collect(),
+    );
+    card.add_row(
+        entries
+            .iter()
+            .map(|(label, _)| Cell::new(&label.dimmed().to_string()))
+            .collect(),
+    );
+    print_table(&card);
+}
+
+/// Prints the most important numbers of the run in one line.
+fn print_compact(run: &Run, options: &RunOptions, inputs: &RunInputs) {
+    let record = closest_world_record(&run.distance);
+    report!(
+        "{} | {} | {} | {} | VDOT {:.1} | {:.3}× WR {}",
+        display_distance(&run.distance, options.use_miles),
+        display_time(&run.time),
+        display_pace_per(
+            &run.average_velocity(),
+            &inputs.pace_unit,
+            options.pace_format
+        ),
+        display_velocity(&run.average_velocity(), options.use_miles),
+        run.vdot(),
+        (run.average_velocity() / record.run.average_velocity()).get::<ratio>(),
+        record.name
+    );
+}
+
+/// Prints how much faster or slower the run was than the last one in the history, if any.
+fn print_last_run_comparison(run: &Run, last: Option<&HistoryEntry>, use_miles: bool) {
+    let last = match last {
+        Some(last) => last,
+        None => {
+            report!("There is no run in your history to compare with yet.");
+            return;
+        }
+    };
+    let unit = unit_distance(use_miles);
+    let difference = unit / run.average_velocity() - unit / last.run.average_velocity();
+    let unit_name = if use_miles {
+        mile::abbreviation()
+    } else {
+        kilometer::abbreviation()
+    };
+    report!(
+        "Compared to your last run of {} in {} on {}{}: {}.",
+        display_distance(&last.run.distance, use_miles),
+        display_time(&last.run.time),
+        display_date(last.timestamp / SECONDS_PER_DAY),
+        match &last.note {
+            Some(note) => format!(" ({})", note),
+            None => String::new(),
+        },
+        if difference <= Time::new::<second>(0.0) {
+            display_judgement(
+                format!("{} per {} faster", display_time(&-difference), unit_name),
+                true,
+            )
+        } else {
+            display_judgement(
+                format!("{} per {} slower", display_time(&difference), unit_name),
+                false,
+            )
+        }
+    );
+}
+
+/// Prints the pace while moving and the average velocity including the stops.
+fn print_elapsed(run: &Run, elapsed: &Time, options: &RunOptions, inputs: &RunInputs) {
+    let overall = Run {
+        distance: run.distance,
+        time: *elapsed,
+    };
+    report!(
+        "While moving, your pace was {}.",
+        display_pace_per(
+            &run.average_velocity(),
+            &inputs.pace_unit,
+            options.pace_format
+        )
+        .emphasized()
+    );
+    report!(
+        "Including {} of stops, your elapsed time was {}, at an average velocity of {} ({}).",
+        display_time(&(*elapsed - run.time)).emphasized(),
+        display_time(elapsed).emphasized(),
+        display_velocity(&overall.average_velocity(), options.use_miles).emphasized(),
+        display_pace_per(
+            &overall.average_velocity(),
+            &inputs.pace_unit,
+            options.pace_format
+        )
+    );
+}
+
+/// Prints the pace of the run relative to the one given with `--compare-to-pace`.
+fn print_pace_comparison(run: &Run, target: &Velocity, options: &RunOptions, inputs: &RunInputs) {
+    let pace_ratio = *target / run.average_velocity();
+    report!(
+        "{}",
+        format!(
+            "Your pace was {:.3} times ({:.1}%) the pace of {}.",
+            pace_ratio.get::<ratio>(),
+            pace_ratio.get::<percent>(),
+            display_pace_per(target, &inputs.pace_unit, options.pace_format)
+        )
+        .emphasized()
+    );
+}
+
+/// Prints the run compared to a custom performance, outside of the verbose comparison table.
+fn print_custom_comparison(run: &Run, custom: &NamedVelocity, options: &RunOptions) {
+    report!(
+        "{}",
+        format!(
+            "Your {} was {:.3} times the one of {}.",
+            match options.compare_basis {
+                CompareBasis::Speed => "average velocity",
+                CompareBasis::Pace | CompareBasis::RecordPace => "pace",
+            },
+            comparison_ratio(run, custom, options.compare_basis),
+            match options.compare_basis {
+                CompareBasis::Speed => display_velocity(&custom.velocity, options.use_miles),
+                CompareBasis::Pace | CompareBasis::RecordPace =>
+                    display_pace(&custom.velocity, options.use_miles),
+            }
+        )
+        .emphasized()
+    );
+}
+
+/// Prints the run compared to a randomly chosen other performance.
+fn print_fun_comparison(run: &Run, options: &RunOptions) {
+    let seed = options.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0)
+    });
+    let performances = other_performances();
+    let performance = &performances[(random_number(seed) % performances.len() as u64) as usize];
+    report!(
+        "You ran at {:.2}× the velocity of {}!",
+        (run.average_velocity() / performance.velocity).get::<ratio>(),
+        performance.name
+    );
+}
+
+/// Prints the medal tier that the VDOT of the run reaches.
+fn print_rating(run: &Run) {
+    let vdot = run.vdot();
+    match tiers().into_iter().find(|tier| vdot >= tier.min_vdot) {
+        Some(tier) => report!(
+            "Your rating: {} (VDOT {:.1}).",
+            format!("[{}]", tier.name.to_uppercase())
+                .color(tier.color)
+                .emphasized(),
+            vdot
+        ),
+        None => report!(
+            "Your rating: no medal yet (VDOT {:.1}), bronze starts at a VDOT of {}.",
+            vdot,
+            tiers().last().expect("there are no tiers!").min_vdot
+        ),
+    }
+}
+
+/// Prints the velocity of the run relative to the world record for the closest standard
+/// distance.
+fn print_percent_of_world_record(run: &Run, options: &RunOptions) {
+    let record = closest_world_record(&run.distance);
+    report!(
+        "{}",
+        format!(
+            "You ran at {:.0}% of WR {} pace.",
+            (run.average_velocity() / record.run.average_velocity()).get::<percent>(),
+            record.name
+        )
+        .emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "percentage = velocity / WR velocity = {} / {} = {:.3}%",
+            display_velocity(&run.average_velocity(), options.use_miles),
+            display_velocity(&record.run.average_velocity(), options.use_miles),
+            (run.average_velocity() / record.run.average_velocity()).get::<percent>()
+        ));
+    }
+}
+
+/// Prints what today's effort corresponds to for the distance of the goal.
+fn print_goal(run: &Run, goal: &Run, options: &RunOptions) {
+    let time = run.predicted_time(&goal.distance, options.model);
+    report!(
+        "Today's effort corresponds to {} for {}, {}.",
+        display_time(&time).emphasized(),
+        display_distance(&goal.distance, options.use_miles),
+        display_goal_delta(&time, &goal.time)
+    );
+    print_required_improvement(run, goal, options.goal.goal_days, options.model);
+}
+
+/// Prints when a race of `--goal-distance` would end at the pace run so far.
+fn print_projection(run: &Run, goal_distance: &str, options: &RunOptions) -> anyhow::Result<()> {
+    let goal_distance =
+        parse_distance(goal_distance).with_context(|| "Could not understand the goal distance.")?;
+    if goal_distance <= run.distance {
+        return Err(anyhow!(
+            "The goal distance has to be longer than the part you have already run."
+        ));
+    }
+    let projected = run.time * (goal_distance / run.distance);
+    if options.progress_bar {
+        print_progress_bar((run.distance / goal_distance).get::<ratio>());
+    }
+    report!(
+        "Keeping up this pace, you will finish the {} in {}.",
+        display_distance(&goal_distance, options.use_miles),
+        display_time(&projected).emphasized()
+    );
+    let goal_time = options
+        .goal_time
+        .as_ref()
+        .map(|goal_time| parse_time(goal_time))
+        .transpose()
+        .with_context(|| "Could not understand the goal time.")?;
+    if let Some(goal_time) = goal_time {
+        if projected <= goal_time {
+            report!(
+                "{}",
+                display_judgement(
+                    format!(
+                        "On pace: finishing ~{} under goal.",
+                        display_time(&(goal_time - projected))
+                    ),
+                    true
+                )
+            );
+        } else {
+            report!(
+                "{}",
+                display_judgement(
+                    format!(
+                        "Off pace: finishing ~{} over goal.",
+                        display_time(&(projected - goal_time))
+                    ),
+                    false
+                )
+            );
+        }
+    }
+    if options.show_formula {
+        print_formula("projected time = goal distance / distance × time");
+    }
+    Ok(())
+}
+
+/// Prints how the run compares to the time predicted from an earlier reference run.
+fn print_expectation(run: &Run, reference: &str, options: &RunOptions) -> anyhow::Result<()> {
+    let reference =
+        parse_run(reference).with_context(|| "Could not understand the reference run.")?;
+    let expected = reference.predicted_time(&run.distance, PredictionModel::Riegel);
+    report!(
+        "Running {} in {}, you should have needed {} today, so you were {}.",
+        display_distance(&reference.distance, options.use_miles),
+        display_time(&reference.time),
+        display_time(&expected).emphasized(),
+        if run.time <= expected {
+            display_judgement(
+                format!("{} faster", display_time(&(expected - run.time))),
+                true,
+            )
+        } else {
+            display_judgement(
+                format!("{} slower", display_time(&(run.time - expected))),
+                false,
+            )
+        }
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "expected time = reference time × (distance / reference distance)^{} = {} × ({} / {})^{}",
+            RIEGEL_EXPONENT,
+            display_seconds(&reference.time),
+            display_distance(&run.distance, options.use_miles),
+            display_distance(&reference.distance, options.use_miles),
+            RIEGEL_EXPONENT
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the pace ranges of the training zones for a threshold pace.
+fn print_training_zones(threshold: &Velocity, options: &RunOptions, inputs: &RunInputs) {
+    report!(
+        "{}",
+        format!(
+            "\nThese are your training zones for a threshold pace of {}:",
+            display_pace_per(threshold, &inputs.pace_unit, options.pace_format)
+        )
+        .emphasized()
+    );
+    let pace_at = |percentage: f64| {
+        display_pace_per(
+            &(*threshold * (100.0 / percentage)),
+            &inputs.pace_unit,
+            options.pace_format,
+        )
+    };
+    let mut zone_table = Table::new();
+    zone_table.set_format(*format::consts::FORMAT_CLEAN);
+    for zone in &pace_zones() {
+        let range = match (zone.slowest, zone.fastest) {
+            (Some(slowest), Some(fastest)) => {
+                format!("{} to {}", pace_at(slowest), pace_at(fastest))
+            }
+            (None, Some(fastest)) => format!("slower than {}", pace_at(fastest)),
+            (Some(slowest), None) => format!("faster than {}", pace_at(slowest)),
+            (None, None) => String::from("any pace"),
+        };
+        zone_table.add_row(row![r -> format!("Zone {}", zone.name), range]);
+    }
+    print_table(&zone_table);
+}
+
+/// Prints the times for the standard distances at the VDOT given with `--target-vdot`.
+fn print_target_vdot(
+    run: &Run,
+    vdot: f64,
+    options: &RunOptions,
+    inputs: &RunInputs,
+) -> anyhow::Result<()> {
+    if vdot <= 0.0 {
+        return Err(anyhow!("The target VDOT must be positive."));
+    }
+    report!(
+        "{}",
+        format!(
+            "\nWith a VDOT of {} (yours today is {:.1}), you would run these times:",
+            vdot,
+            run.vdot()
+        )
+        .emphasized()
+    );
+    let mut vdot_table = Table::new();
+    vdot_table.set_format(*format::consts::FORMAT_CLEAN);
+    for distance in &standard_distances(options.use_miles) {
+        let target = Run::for_vdot(&distance.distance, vdot);
+        vdot_table.add_row(row![
+            r -> distance.name,
+            display_time(&target.time),
+            display_pace_per(&target.average_velocity(), &inputs.pace_unit, options.pace_format)
+        ]);
+    }
+    print_table(&vdot_table);
+    Ok(())
+}
+
+/// Prints how long the same effort would have taken on flat ground.
+fn print_flat_equivalent(run: &Run, elevation: &Length, options: &RunOptions) {
+    report!(
+        "\nOn flat ground, the same effort would have taken you about {}.",
+        display_time(&run.equivalent_flat_time(elevation)).emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "flat time = time / grade effort = {} / {:.3} = {}",
+            display_seconds(&run.time),
+            run.grade_effort(elevation),
+            display_seconds(&run.equivalent_flat_time(elevation))
+        ));
+    }
+}
+
+/// Prints the pace for repetitions on the track at the VDOT of the run.
+fn print_repetition_pace(run: &Run, options: &RunOptions) {
+    let repetition = Run::for_vdot(&Length::new::<meter>(REPETITION_PACE_DISTANCE), run.vdot())
+        .time_for_distance(&Length::new::<meter>(REPETITION_DISTANCE));
+    report!(
+        "\nFor repetitions on the track, your 400 m pace would be {} (following Jack Daniels' R pace).",
+        format!("{} per 400 m", display_clock_time(&repetition)).emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "400 m pace = 400 m / {} m × time for {} m at your VDOT of {:.1}",
+            REPETITION_PACE_DISTANCE,
+            REPETITION_PACE_DISTANCE,
+            run.vdot()
+        ));
+    }
+}
+
+/// Prints the effort score from the pace relative to the threshold and the heart rate, if
+/// either is known.
+fn print_effort_score(
+    run: &Run,
+    heart_rate: Option<u32>,
+    options: &RunOptions,
+    inputs: &RunInputs,
+) {
+    let pace_intensity = inputs.threshold.map(|threshold| {
+        (run.average_velocity() / threshold).get::<ratio>()
+            * run.grade_effort(&inputs.elevation_or_flat())
+    });
+    let heart_rate_fraction = match (heart_rate, options.max_heart_rate) {
+        (Some(heart_rate), Some(max)) if max > 0 => Some(f64::from(heart_rate) / f64::from(max)),
+        _ => None,
+    };
+    if let Some(score) = effort_score(pace_intensity, heart_rate_fraction) {
+        report!(
+            "\nYour effort score was {} (from {}).",
+            format!("{:.0} of 100", score).emphasized(),
+            match (pace_intensity, heart_rate_fraction) {
+                (Some(_), Some(_)) => "pace and heart rate",
+                (Some(_), None) => "pace",
+                _ => "heart rate",
+            }
+        );
+        if options.show_formula {
+            print_formula(&format!(
+                "pace score = 100 × grade adjusted velocity / threshold velocity / {}",
+                EFFORT_MAX_PACE_INTENSITY
+            ));
+            print_formula(&format!(
+                "heart rate score = 100 × (heart rate / max heart rate − {0}) / (1 − {0})",
+                EFFORT_MIN_HEART_RATE_FRACTION
+            ));
+            print_formula(&format!(
+                "effort score = {} × pace score + {} × heart rate score, or the one available",
+                EFFORT_PACE_WEIGHT,
+                1.0 - EFFORT_PACE_WEIGHT
+            ));
+        }
+    }
+}
+
+/// Prints where the run would roughly place in a typical race with the given number of
+/// runners, with the time for the race distance predicted by the model.
+fn print_field_position(run: &Run, field_size: usize, model: PredictionModel) {
+    let typical = closest_by_distance(typical_finish_times(), &run.distance)
+        .expect("there are no typical finish times!");
+    let time = run.predicted_time(&typical.run.distance, model);
+    let position = |median_factor: f64| {
+        let fraction = fraction_faster(&time, &(typical.run.time * median_factor));
+        ((fraction * field_size as f64).ceil() as usize)
+            .max(1)
+            .min(field_size)
+    };
+    let best = position(1.0 + FINISH_TIME_MEDIAN_UNCERTAINTY);
+    let worst = position(1.0 - FINISH_TIME_MEDIAN_UNCERTAINTY);
+    report!(
+        "\nIn a typical {} race with {} runners, you would finish {}.",
+        typical.name,
+        field_size,
+        if best <= TOP_POSITIONS_THRESHOLD {
+            format!(
+                "among the first {}",
+                worst.max(TOP_POSITIONS_THRESHOLD).min(field_size)
+            )
+            .emphasized()
+        } else {
+            format!(
+                "roughly between {} and {}",
+                display_ordinal(best),
+                display_ordinal(worst)
+            )
+            .emphasized()
+        }
+    );
+}
+
+/// Prints the energy burned during the run.
+fn print_calories(run: &Run, weight: &Mass, options: &RunOptions, inputs: &RunInputs) {
+    let kilocalories = match options.calories_model {
+        CaloriesModel::Simple => run.simple_kilocalories(weight),
+        CaloriesModel::Acsm => run.acsm_kilocalories(weight, &inputs.elevation_or_flat()),
+    };
+    report!(
+        "\nYou burned about {}.",
+        format!("{:.0} kcal", kilocalories).emphasized()
+    );
+    if options.show_formula {
+        print_formula(&match options.calories_model {
+            CaloriesModel::Simple => format!(
+                "energy = {} kcal per kg and km × weight × distance",
+                KILOCALORIES_PER_KILOGRAM_AND_KILOMETER
+            ),
+            CaloriesModel::Acsm => format!(
+                "energy = ({} × speed + {} × speed × uphill grade + {}) ml/kg/min × weight × time × {} kcal/l, with speed in m/min",
+                ACSM_HORIZONTAL_OXYGEN_COST,
+                ACSM_VERTICAL_OXYGEN_COST,
+                RESTING_OXYGEN_UPTAKE,
+                KILOCALORIES_PER_LITER_OXYGEN
+            ),
+        });
+    }
+}
+
+/// Prints a similar effort on an indoor rower and on a bike erg.
+fn print_cross_training(run: &Run, options: &RunOptions, inputs: &RunInputs) {
+    let weight = inputs
+        .weight
+        .unwrap_or_else(|| Mass::new::<kilogram>(REFERENCE_BODY_WEIGHT));
+    report!(
+        "\nA similar effort would roughly be a 500 m split of {} on an indoor rower or {} on a bike erg (very approximate).",
+        display_time(&run.equivalent_rowing_split(&weight)).emphasized(),
+        format!("{:.0} W", run.equivalent_cycling_power(&weight)).emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "bike power = {} J/kg/m × velocity × weight × {}",
+            RUNNING_ENERGY_PER_KILOGRAM_AND_METER, CYCLING_EFFICIENCY
+        ));
+        print_formula(&format!(
+            "rowing split = 500 m × ({} / ({} × bike power))^(1/3)",
+            ROWING_PACE_CONSTANT, ROWING_TO_CYCLING_POWER
+        ));
+    }
+}
+
+/// Prints the performance score adjusted for the body mass index.
+fn print_body_mass_score(
+    run: &Run,
+    weight: &Mass,
+    height: &str,
+    options: &RunOptions,
+) -> anyhow::Result<()> {
+    let height = parse_distance(height).with_context(|| "Could not understand the height.")?;
+    report!(
+        "\nYour body-mass adjusted performance score is {} (a rough heuristic, your BMI is {:.1}).",
+        format!("{:.1}", body_mass_adjusted_score(run, weight, &height)).emphasized(),
+        body_mass_index(weight, &height)
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "score = velocity in km/h × BMI / {} = {:.3} × {:.3} / {} = {:.3}",
+            REFERENCE_BODY_MASS_INDEX,
+            run.average_velocity().get::<kilometer_per_hour>(),
+            body_mass_index(weight, &height),
+            REFERENCE_BODY_MASS_INDEX,
+            body_mass_adjusted_score(run, weight, &height)
+        ));
+    }
+    Ok(())
+}
+
+/// Prints the additional information of `--verbose`.
+fn print_verbose(
+    run: &Run,
+    recorded_heart_rate: Option<u32>,
+    options: &RunOptions,
+    inputs: &RunInputs,
+) -> anyhow::Result<()> {
+    if let Some(elevation) = &inputs.elevation {
+        print_flat_equivalent(run, elevation, options);
+    }
+    print_repetition_pace(run, options);
+    print_effort_score(
+        run,
+        options.heart_rate.or(recorded_heart_rate),
+        options,
+        inputs,
+    );
+    if let Some(points) = world_athletics_points(run) {
+        report!(
+            "\nThis is worth {} (following the World Athletics scoring tables).",
+            format!("≈ {} WA points", points).emphasized()
+        );
+    }
+    if let Some(field_size) = options.field_size {
+        print_field_position(run, field_size, options.model);
+    }
+    if let Some(weight) = &inputs.weight {
+        print_calories(run, weight, options, inputs);
+    }
+    if options.cross_train {
+        print_cross_training(run, options, inputs);
+    }
+    if let (Some(weight), Some(height)) = (&inputs.weight, &options.height) {
+        print_body_mass_score(run, weight, height, options)?;
+    }
+
+    if !options.no_distance_table {
+        report!(
+            "{}",
+            "\nThis is how long you would have needed for other distances:".emphasized()
+        );
+        print_predictions(
+            run,
+            options.use_miles,
+            options.show_formula,
+            options.model,
+            inputs.goal.as_ref(),
+            options.relative,
+            options.race_temp,
+            options.humidity,
+        );
+        if let Some(goal) = &inputs.goal {
+            print_required_improvement(run, goal, options.goal.goal_days, options.model);
+        }
+    }
+    print_carb_needs(
+        run,
+        options.use_miles,
+        options.model,
+        options.carbs_per_hour,
+    );
+
+    if !options.no_comparisons {
+        let velocities = selected_performances(run, options)?;
+        if velocities.is_empty() {
+            report!("\nNo other performance matches your selection.");
+        } else {
+            print_comparisons(
+                run,
+                &velocities,
+                options.compare_basis,
+                options.use_miles,
+                &inputs.pace_unit,
+                options.pace_format,
+                options.show_formula,
+                options
+                    .sex
+                    .filter(|sex| *sex == Sex::Female)
+                    .map(|_| gender_gap(&run.distance)),
+                options.age.map(age_grade_factor),
+                options.compare_notes,
+            );
+        }
+        if options.compare_equivalent {
+            print_equivalent_comparisons(
+                run,
+                options.compare_basis,
+                options.use_miles,
+                options.show_formula,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn report_run(options: &RunOptions) -> anyhow::Result<()> {
+    let activity = read_activity(&options.run)?;
+    let recorded_heart_rate = activity.as_ref().and_then(Activity::average_heart_rate);
+    let run = match &activity {
+        Some(activity) => activity.run(),
+        None => Run::from_options(&options.run, options.input_in_miles())
+            .with_context(|| "Could not understand the passed arguments.")?,
+    };
+    let inputs = RunInputs::parse(options, &run)?;
+    if let Some(warning) = implausibility(&run) {
+        if options.strict {
+            return Err(anyhow!(warning.message));
+        }
+        eprintln!("{} {}", "Warning:".yellow().bold(), warning.message);
+    }
+    if let Some(activity) = &activity {
+        print_activity(activity, options.use_miles);
+    }
+    if options.explain {
+        print_explanation(&run, options);
+    }
+    // The history is read before saving, so that the latest run is not this one.
+    let last_run = if options.compare_to_last {
+        let path = history_path(&options.history)?;
+        Some(
+            read_history(&path)?
+                .into_iter()
+                .max_by_key(|entry| entry.timestamp),
+        )
+    } else {
+        None
+    };
+    print_report(
+        &run,
+        recorded_heart_rate,
+        last_run.as_ref().map(Option::as_ref),
+        options,
+        &inputs,
+    )?;
+    // The run is only saved once everything about it has been checked.
+    if options.save {
+        let path = history_path(&options.history)?;
+        append_history(
+            &path,
+            &HistoryEntry {
+                timestamp: current_timestamp(),
+                run: Run {
+                    distance: run.distance,
+                    time: run.time,
+                },
+                note: options.note.clone(),
+                tags: options.tags.as_deref().map(parse_tags).unwrap_or_default(),
+            },
+        )
+        .with_context(|| format!("Could not save the run to \"{}\".", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Prints the report for a run, in the style chosen by the options.
+fn print_report(
+    run: &Run,
+    recorded_heart_rate: Option<u32>,
+    last_run: Option<Option<&HistoryEntry>>,
+    options: &RunOptions,
+    inputs: &RunInputs,
+) -> anyhow::Result<()> {
+    if let Some(path) = &options.compare_out {
+        write_comparisons(
+            path,
+            run,
+            &selected_performances(run, options)?,
+            options.compare_basis,
+            options.use_miles,
+        )
+        .with_context(|| format!("Could not export the comparison to \"{}\".", path))?;
+    }
+
+    if options.share {
+        print_share(run, options, inputs);
+        return Ok(());
+    }
+    if options.style == OutputStyle::Strava {
+        print_strava_card(run, options, inputs);
+        return Ok(());
+    }
+    if options.compact {
+        print_compact(run, options, inputs);
+        return Ok(());
+    }
+
+    print_run(run, options.use_miles, options.show_formula);
+    if let Some(last) = last_run {
+        print_last_run_comparison(run, last, options.use_miles);
+    }
+    if let Some(elevation) = inputs.elevation.filter(|_| options.vertical) {
+        print_vertical(run, &elevation, options)?;
+    }
+    if options.pace_per.is_some() {
+        report!(
+            "Your pace was {}.",
+            display_pace_per(
+                &run.average_velocity(),
+                &inputs.pace_unit,
+                options.pace_format
+            )
+            .emphasized()
+        );
+    }
+    if options.mirror {
+        print_mirror(run);
+    }
+    if options.standardize {
+        print_standardized(run, options, inputs)?;
+    }
+
+    if let Some(main) = &inputs.main_set {
+        report!(
+            "Your main set was {} in {}, at an average velocity of {} ({}).",
+            display_distance(&main.distance, options.use_miles).emphasized(),
+            display_time(&main.time).emphasized(),
+            display_velocity(&main.average_velocity(), options.use_miles).emphasized(),
+            display_pace_per(
+                &main.average_velocity(),
+                &inputs.pace_unit,
+                options.pace_format
+            )
+        );
+    }
+    if let Some(elapsed) = &inputs.elapsed {
+        print_elapsed(run, elapsed, options, inputs);
+    }
+
+    if !inputs.splits.is_empty() {
+        print_splits(&inputs.splits);
+        if options.fade_analysis {
+            print_fade_analysis(&inputs.splits);
+        }
+        if options.best_split {
+            print_best_split(&inputs.splits);
+        }
+    }
+
+    if let Some(pace) = &options.compare_to_pace {
+        let target = parse_pace(pace, options.input_in_miles())
+            .with_context(|| "Could not understand the pace to compare with.")?;
+        print_pace_comparison(run, &target, options, inputs);
+    }
+    if let Some(custom) = custom_performance(options)?.filter(|_| !options.verbose) {
+        print_custom_comparison(run, &custom, options);
+    }
+    if options.fun {
+        print_fun_comparison(run, options);
+    }
+    if options.rate {
+        print_rating(run);
+    }
+    if options.compare_percent_of_wr {
+        print_percent_of_world_record(run, options);
+    }
+    if let Some(goal) = inputs
+        .goal
+        .as_ref()
+        .filter(|_| !options.verbose || options.no_distance_table)
+    {
+        print_goal(run, goal, options);
+    }
+    if let Some(goal_distance) = &options.goal_distance {
+        print_projection(run, goal_distance, options)?;
+    }
+    if let Some(reference) = &options.predict_from {
+        print_expectation(run, reference, options)?;
+    }
+    if let Some(threshold) = &inputs.threshold {
+        print_training_zones(threshold, options, inputs);
+    }
+    if let Some(vdot) = options.target_vdot {
+        print_target_vdot(run, vdot, options, inputs)?;
+    }
+
+    if options.verbose {
+        print_verbose(run, recorded_heart_rate, options, inputs)?;
+    }
+    Ok(())
+}
+
+fn report_predictions(options: &PredictOptions) -> anyhow::Result<()> {
+    let run = Run::from_options(&options.run, options.use_miles)
+        .with_context(|| "Could not understand the passed arguments.")?;
+    let goal = parse_goal(&options.goal).with_context(|| "Could not understand the goal.")?;
+    report!(
+        "{}",
+        format!(
+            "This is how long running {} in {} corresponds to for other distances:",
+            display_distance(&run.distance, options.use_miles),
+            display_time(&run.time)
+        )
+        .emphasized()
+    );
+    print_predictions(
+        &run,
+        options.use_miles,
+        options.show_formula,
+        options.model,
+        goal.as_ref(),
+        options.relative,
+        options.race_temp,
+        options.humidity,
+    );
+    if let Some(goal) = &goal {
+        print_required_improvement(&run, goal, options.goal.goal_days, options.model);
+    }
+    Ok(())
+}
+
+fn report_conversion(options: &ConvertOptions) -> anyhow::Result<()> {
+    let distance = parse_distance(&options.distance)
+        .with_context(|| "Could not understand the passed arguments.")?;
+
+    let mut conv_table = Table::new();
+    conv_table.set_format(*format::consts::FORMAT_CLEAN);
+    conv_table.add_row(row![r -> format!("{:.3}", distance.get::<meter>()), meter::abbreviation()]);
+    conv_table.add_row(
+        row![r -> format!("{:.3}", distance.get::<kilometer>()), kilometer::abbreviation()],
+    );
+    conv_table.add_row(row![r -> format!("{:.3}", distance.get::<mile>()), mile::abbreviation()]);
+    conv_table.add_row(row![r -> format!("{:.3}", distance.get::<yard>()), yard::abbreviation()]);
+    conv_table.add_row(row![r -> format!("{:.3}", distance.get::<foot>()), foot::abbreviation()]);
+
+    report!(
+        "{}",
+        format!("{} corresponds to:", options.distance).emphasized()
+    );
+    print_table(&conv_table);
+    Ok(())
+}
+
+/// A row of a CSV file given with `--input-file`. Other columns, like a date or notes, are
+/// ignored.
+#[derive(Deserialize)]
+struct InputFileRow {
+    distance: String,
+    time: String,
+}
+
+/// Reads the runs from a CSV file with a header naming its columns.
+fn read_input_file(path: &str) -> anyhow::Result<Vec<Run>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Could not read the input file \"{}\".", path))?;
+    let headers = reader
+        .headers()
+        .with_context(|| format!("Could not read the header of \"{}\".", path))?
+        .clone();
+    reader
+        .records()
+        .map(|record| {
+            // The lines are counted by the reader, since it skips empty ones.
+            let record = record.map_err(|error| {
+                let context = match error.position() {
+                    Some(position) => {
+                        format!("Could not read line {} of \"{}\".", position.line(), path)
+                    }
+                    None => format!("Could not read \"{}\".", path),
+                };
+                anyhow::Error::new(error).context(context)
+            })?;
+            let line = record.position().map_or(0, csv::Position::line);
+            let row = record
+                .deserialize::<InputFileRow>(Some(&headers))
+                .with_context(|| format!("Could not read line {} of \"{}\".", line, path))?;
+            Run::from_options(
+                &RunArguments {
+                    distance: Some(row.distance),
+                    time: Some(row.time),
+                    pace: None,
+                    fit: None,
+                    gpx: None,
+                    smart_units: false,
+                },
+                false,
+            )
+            .with_context(|| {
+                format!(
+                    "Could not understand the run in line {} of \"{}\".",
+                    line, path
+                )
+            })
+        })
+        .collect()
+}
+
+/// Keeps only the fastest run at each distance, sorted by distance.
+fn power_curve(mut runs: Vec<Run>) -> Vec<Run> {
+    runs.sort_by(|a, b| {
+        a.distance
+            .partial_cmp(&b.distance)
+            .unwrap()
+            .then(a.time.partial_cmp(&b.time).unwrap())
+    });
+    let mut curve: Vec<Run> = Vec::new();
+    for run in runs {
+        // The runs at the same distance are sorted by time, so the first one is the best.
+        match curve.last() {
+            Some(best) if is_same_distance(&best.distance, &run.distance) => {}
+            _ => curve.push(run),
+        }
+    }
+    curve
+}
+
+#[derive(Serialize)]
+struct PowerCurvePoint {
+    distance_in_meters: f64,
+    time_in_seconds: f64,
+}
+
+fn write_power_curve(path: &str, curve: &[Run]) -> anyhow::Result<()> {
+    let points = curve
+        .iter()
+        .map(|run| PowerCurvePoint {
+            distance_in_meters: run.distance.get::<meter>(),
+            time_in_seconds: run.time.get::<second>(),
+        })
+        .collect::<Vec<_>>();
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_ref().map(|extension| &extension[..]) {
+        Some("json") => {
+            let mut file = std::fs::File::create(path)?;
+            serde_json::to_writer_pretty(&mut file, &points)?;
+            std::io::Write::write_all(&mut file, b"\n")?;
+        }
+        Some("csv") => {
+            let mut writer = csv::Writer::from_path(path)?;
+            for point in &points {
+                writer.serialize(point)?;
+            }
+            writer.flush()?;
+        }
+        _ => {
+            return Err(anyhow!(
+                "The file name must end in .json or .csv to tell the format."
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn report_summary(options: &SummaryOptions) -> anyhow::Result<()> {
+    if options.runs.len() % 2 != 0 {
+        return Err(anyhow!(
+            "Every run needs a distance and a time, but an odd number of values was given."
+        ));
+    }
+    let mut runs = options
+        .runs
+        .chunks(2)
+        .map(|pair| {
+            Run::from_options(
+                &RunArguments {
+                    distance: Some(pair[0].clone()),
+                    time: Some(pair[1].clone()),
+                    pace: None,
+                    fit: None,
+                    gpx: None,
+                    smart_units: false,
+                },
+                false,
+            )
+            .with_context(|| format!("Could not understand the run \"{} {}\".", pair[0], pair[1]))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if let Some(path) = &options.input_file {
+        runs.extend(read_input_file(path)?);
+    }
+    let tag = options.tag.as_deref();
+    if tag.is_some() {
+        runs.extend(
+            read_tagged_history(&options.history, tag)?
+                .into_iter()
+                .map(|entry| entry.run),
+        );
+    }
+
+    let mut has_output = false;
+    if options.since_pr {
+        print_personal_records(&options.history, tag, options.use_miles)?;
+        has_output = true;
+    }
+    if let Some(distance) = &options.trend {
+        if has_output {
+            println!();
+        }
+        let distance =
+            parse_distance(distance).with_context(|| "Could not understand the trend distance.")?;
+        print_trend(&options.history, tag, &distance, options.use_miles)?;
+        has_output = true;
+    }
+    if let Some(period) = options.group_by {
+        if has_output {
+            println!();
+        }
+        print_training_log(
+            &options.history,
+            tag,
+            period.unwrap_or(Period::Week),
+            options.use_miles,
+        )?;
+        has_output = true;
+    }
+    if let Some(path) = &options.power_curve {
+        if has_output {
+            println!();
+        }
+        let mut all_runs = runs
+            .iter()
+            .map(|run| Run {
+                distance: run.distance,
+                time: run.time,
+            })
+            .collect::<Vec<_>>();
+        // With a tag, the runs with it are already among the given ones.
+        if options.include_history && tag.is_none() {
+            all_runs.extend(
+                read_history(&history_path(&options.history)?)?
+                    .into_iter()
+                    .map(|entry| entry.run),
+            );
+        }
+        let curve = power_curve(all_runs);
+        write_power_curve(path, &curve)
+            .with_context(|| format!("Could not export the power curve to \"{}\".", path))?;
+        report!(
+            "Wrote your best times at {} distances to \"{}\".",
+            curve.len(),
+            path
+        );
+        has_output = true;
+    }
+    if has_output {
+        if runs.is_empty() {
+            return Ok(());
+        }
+        println!();
+    } else if runs.is_empty() {
+        return match tag {
+            Some(tag) => Err(anyhow!(
+                "There are no runs with the tag \"{}\" to summarise.",
+                tag
+            )),
+            None => Err(anyhow!("No runs to summarise were given.")),
+        };
+    }
+
+    let mut run_table = Table::new();
+    run_table.set_format(*format::consts::FORMAT_CLEAN);
+    for run in &runs {
+        run_table.add_row(row![
+            r -> display_distance(&run.distance, options.use_miles),
+            display_time(&run.time),
+            r -> display_velocity(&run.average_velocity(), options.use_miles),
+            r -> display_pace(&run.average_velocity(), options.use_miles)
+        ]);
+    }
+    print_table(&run_table);
+
+    let total = Run {
+        distance: runs
+            .iter()
+            .map(|run| run.distance)
+            .fold(Length::new::<meter>(0.0), |a, b| a + b),
+        time: runs
+            .iter()
+            .map(|run| run.time)
+            .fold(Time::new::<second>(0.0), |a, b| a + b),
+    };
+    report!(
+        "\nIn {} runs, you ran {} in {}.",
+        runs.len(),
+        display_distance(&total.distance, options.use_miles).emphasized(),
+        display_time(&total.time).emphasized()
+    );
+    report!(
+        "{}",
+        format!(
+            "Your overall average velocity was {}.",
+            display_velocity(&total.average_velocity(), options.use_miles)
+        )
+        .emphasized()
+    );
+
+    if options.profile {
+        print_pace_profile(&runs, options.use_miles)?;
+    }
+    Ok(())
+}
+
+/// Fits the pace (in seconds per unit distance) to the logarithm of the distance (in unit
+/// distances) by linear regression and prints the trend.
+fn print_pace_profile(runs: &[Run], use_miles: bool) -> anyhow::Result<()> {
+    let unit = unit_distance(use_miles);
+    let points = runs
+        .iter()
+        .map(|run| {
+            (
+                (run.distance / unit).get::<ratio>().ln(),
+                (unit / run.average_velocity()).get::<second>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|point| point.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|point| point.1).sum::<f64>() / count;
+    let spread = points
+        .iter()
+        .map(|point| (point.0 - mean_x).powi(2))
+        .sum::<f64>();
+    if spread <= 0.0 {
+        return Err(anyhow!(
+            "A pace profile needs runs of at least two different distances."
+        ));
+    }
+    let slope = points
+        .iter()
+        .map(|point| (point.0 - mean_x) * (point.1 - mean_y))
+        .sum::<f64>()
+        / spread;
+    let intercept = mean_y - slope * mean_x;
+
+    report!(
+        "\nFitted trend: pace in seconds = {:.3} + {:.3} × ln(distance in {}).",
+        intercept,
+        slope,
+        if use_miles { "mi" } else { "km" }
+    );
+    let per_doubling = Time::new::<second>((slope * 2.0_f64.ln()).abs());
+    report!(
+        "{}",
+        format!(
+            "Each time the distance doubles, your pace gets {} {}.",
+            display_time(&per_doubling),
+            if slope >= 0.0 { "slower" } else { "faster" }
+        )
+        .emphasized()
+    );
+    Ok(())
+}
+
+/// The width of a pace band file in points, about the width of a wristband.
+const PACE_BAND_WIDTH: f64 = 100.0;
+/// The height of a line on a pace band file in points.
+const PACE_BAND_LINE_HEIGHT: f64 = 12.0;
+/// The margin around the text of a pace band file in points.
+const PACE_BAND_MARGIN: f64 = 8.0;
+
+/// Creates a PDF document with one page that shows the lines of a pace band.
+fn pace_band_pdf(lines: &[String]) -> String {
+    let height = 2.0 * PACE_BAND_MARGIN + PACE_BAND_LINE_HEIGHT * lines.len() as f64;
+    let mut content = format!(
+        "BT /F1 9 Tf {} TL {} {} Td\n",
+        PACE_BAND_LINE_HEIGHT,
+        PACE_BAND_MARGIN,
+        height - PACE_BAND_MARGIN - 9.0
+    );
+    for line in lines {
+        let escaped = line
+            .replace('\\', "\\\\")
+            .replace('(', "\\(")
+            .replace(')', "\\)");
+        content.push_str(&format!("({}) Tj T*\n", escaped));
+    }
+    content.push_str("ET\n");
+
+    let objects = [
+        String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+        String::from("<< /Type /Pages /Kids [3 0 R] /Count 1 >>"),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>",
+            PACE_BAND_WIDTH, height
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+        String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+    ];
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+    }
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+    pdf
+}
+
+/// Creates an SVG image that shows the lines of a pace band.
+fn pace_band_svg(lines: &[String]) -> String {
+    let height = 2.0 * PACE_BAND_MARGIN + PACE_BAND_LINE_HEIGHT * lines.len() as f64;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"Helvetica, sans-serif\" font-size=\"9\">\n",
+        PACE_BAND_WIDTH, height
+    );
+    for (index, line) in lines.iter().enumerate() {
+        let escaped = line
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\">{}</text>\n",
+            PACE_BAND_MARGIN,
+            PACE_BAND_MARGIN + PACE_BAND_LINE_HEIGHT * (index + 1) as f64 - 3.0,
+            escaped
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn report_live(options: &LiveOptions) -> anyhow::Result<()> {
+    let lap_distance = parse_distance(&options.lap_distance)
+        .with_context(|| "Could not understand the lap distance.")?;
+    if lap_distance <= Length::new::<meter>(0.0) {
+        return Err(anyhow!("The lap distance must be longer than zero."));
+    }
+
+    report!(
+        "Press Enter at the end of each lap of {}, and Ctrl-D (or type q) when you are done.",
+        display_distance(&lap_distance, options.use_miles)
+    );
+    let start = std::time::Instant::now();
+    let mut splits = Vec::new();
+    let mut last_lap = Time::new::<second>(0.0);
+    let stdin = std::io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.with_context(|| "Could not read the lap presses.")?;
+        if line.trim() == "q" {
+            break;
+        }
+        let elapsed = Time::new::<second>(start.elapsed().as_secs_f64());
+        splits.push(elapsed - last_lap);
+        last_lap = elapsed;
+        report!(
+            "Lap {}: {} ({} in total)",
+            splits.len(),
+            display_clock_time(&splits[splits.len() - 1]).emphasized(),
+            display_clock_time(&elapsed)
+        );
+    }
+    if splits.is_empty() {
+        return Err(anyhow!("No laps were recorded."));
+    }
+
+    let mut lap_table = Table::new();
+    lap_table.set_format(*format::consts::FORMAT_CLEAN);
+    let mut total = Time::new::<second>(0.0);
+    for (index, split) in splits.iter().enumerate() {
+        total += *split;
+        let lap = Run {
+            distance: lap_distance,
+            time: *split,
+        };
+        lap_table.add_row(row![
+            r -> format!("lap {}", index + 1),
+            r -> display_time(split),
+            r -> display_clock_time(&total),
+            display_pace(&lap.average_velocity(), options.use_miles)
+        ]);
+    }
+    report!("{}", "\nYour laps:".emphasized());
+    print_table(&lap_table);
+
+    let run = Run {
+        distance: lap_distance * splits.len() as f64,
+        time: total,
+    };
+    println!();
+    print_run(&run, options.use_miles, false);
+    report!(
+        "Your average pace was {}.",
+        display_pace(&run.average_velocity(), options.use_miles)
+    );
+    if splits.len() > 1 {
+        print_splits(&splits);
+    }
+    Ok(())
+}
+
+/// The largest part of the weekly distance to run in the long run.
+const LONG_RUN_FRACTION: f64 = 0.4;
+/// The longest long run in km, even when preparing for longer races.
+const MAX_LONG_RUN: f64 = 32.0;
+/// The shortest long run in km to build up to, even when preparing for shorter races.
+const MIN_LONG_RUN: f64 = 10.0;
+/// By how much the weekly distance may grow from one week to the next.
+const MAX_WEEKLY_INCREASE: f64 = 0.1;
+/// Every this many weeks, the weekly distance is reduced to recover.
+const RECOVERY_WEEK_INTERVAL: usize = 4;
+/// The part of the previous weekly distance to run in a recovery week.
+const RECOVERY_WEEK_FRACTION: f64 = 0.8;
+/// The parts of the peak weekly distance to run in the last weeks, ending with the race week.
+const TAPER_FRACTIONS: [f64; 2] = [0.75, 0.5];
+
+/// A week of a training plan.
+struct PlanWeek {
+    distance: Length,
+    note: &'static str,
+}
+
+/// Plans the weekly distance for the given number of weeks: growing by at most 10% per week
+/// towards the peak, with a recovery week every fourth week and a taper before the race.
+fn plan_weeks(current: Length, peak: Length, weeks: usize) -> Vec<PlanWeek> {
+    let taper_weeks = if weeks >= 8 {
+        2
+    } else if weeks >= 3 {
+        1
+    } else {
+        0
+    };
+    let mut plan = Vec::with_capacity(weeks);
+    let mut build = current;
+    let mut highest = current;
+    for week in 0..weeks - taper_weeks {
+        if week % RECOVERY_WEEK_INTERVAL == RECOVERY_WEEK_INTERVAL - 1 {
+            plan.push(PlanWeek {
+                distance: build * RECOVERY_WEEK_FRACTION,
+                note: "recovery",
+            });
+        } else {
+            if week > 0 {
+                build = (build * (1.0 + MAX_WEEKLY_INCREASE)).min(peak.max(current));
+            }
+            highest = highest.max(build);
+            plan.push(PlanWeek {
+                distance: build,
+                note: "",
+            });
+        }
+    }
+    let taper = &TAPER_FRACTIONS[TAPER_FRACTIONS.len() - taper_weeks..];
+    for (index, fraction) in taper.iter().enumerate() {
+        plan.push(PlanWeek {
+            distance: highest * *fraction,
+            note: if index == taper.len() - 1 {
+                "race week"
+            } else {
+                "taper"
+            },
+        });
+    }
+    plan
+}
+
+fn report_plan(options: &PlanOptions) -> anyhow::Result<()> {
+    let goal_distance = parse_distance(&options.goal_distance)
+        .with_context(|| "Could not understand the goal distance.")?;
+    if goal_distance <= Length::new::<meter>(0.0) {
+        return Err(anyhow!("The goal distance must be longer than zero."));
+    }
+    if options.weeks == 0 {
+        return Err(anyhow!("The plan must have at least one week."));
+    }
+    let peak_long_run = (goal_distance * 0.75)
+        .max(Length::new::<kilometer>(MIN_LONG_RUN))
+        .min(Length::new::<kilometer>(MAX_LONG_RUN));
+    let peak = peak_long_run / LONG_RUN_FRACTION;
+    let current = match &options.current {
+        Some(current) => parse_distance(current)
+            .with_context(|| "Could not understand your current weekly distance.")?,
+        None => peak * 0.5,
+    };
+    if current <= Length::new::<meter>(0.0) {
+        return Err(anyhow!(
+            "Your current weekly distance must be longer than zero."
+        ));
+    }
+
+    if let Some(recent) = &options.recent {
+        let recent = parse_run(recent).with_context(|| "Could not understand the recent run.")?;
+        report!(
+            "With your current fitness, you could run the {} in about {}.",
+            display_distance(&goal_distance, options.use_miles),
+            display_time(&recent.predicted_time(&goal_distance, PredictionModel::Riegel))
+                .emphasized()
+        );
+    }
+    report!(
+        "{}",
+        format!(
+            "This is how you could build up to a peak of {} per week for the {} in {} weeks:",
+            display_distance(&peak, options.use_miles),
+            display_distance(&goal_distance, options.use_miles),
+            options.weeks
+        )
+        .emphasized()
+    );
+    let mut plan_table = Table::new();
+    plan_table.set_format(*format::consts::FORMAT_CLEAN);
+    for (index, week) in plan_weeks(current, peak, options.weeks).iter().enumerate() {
+        let long_run = (week.distance * LONG_RUN_FRACTION).min(peak_long_run);
+        plan_table.add_row(row![
+            r -> format!("week {}", index + 1),
+            r -> display_distance(&week.distance, options.use_miles),
+            format!(
+                "long run {}",
+                display_distance(&long_run, options.use_miles)
+            ),
+            week.note
+        ]);
+    }
+    print_table(&plan_table);
+    Ok(())
+}
+
+fn report_band(options: &BandOptions) -> anyhow::Result<()> {
+    let target = Run::from_options(
+        &RunArguments {
+            distance: Some(options.distance.clone()),
+            time: Some(options.time.clone()),
+            pace: None,
+            fit: None,
+            gpx: None,
+            smart_units: false,
+        },
+        false,
+    )
+    .with_context(|| "Could not understand the passed arguments.")?;
+    let unit = unit_distance(options.use_miles);
+    if let Some(round_to) = options.round_to {
+        if round_to <= 0.0 {
+            return Err(anyhow!(
+                "The number of seconds to round to must be greater than zero."
+            ));
+        }
+    }
+    // Every split time is derived from the rounded pace rather than rounded on its own, so that
+    // the time between two consecutive splits is always exactly that pace.
+    let pace = match options.round_to {
+        Some(round_to) => round_time_to(&(unit / target.average_velocity()), round_to),
+        None => unit / target.average_velocity(),
+    };
+    let time_for_distance = |distance: &Length| {
+        let time = pace * (*distance / unit);
+        match options.round_to {
+            Some(round_to) => round_time_to(&time, round_to),
+            None => time,
+        }
+    };
+    let finish_time = time_for_distance(&target.distance);
+
+    let mut lines = vec![format!(
+        "{} in {}",
+        display_distance(&target.distance, options.use_miles),
+        display_clock_time(&finish_time)
+    )];
+    let mut band_table = Table::new();
+    band_table.set_format(*format::consts::FORMAT_CLEAN);
+    let mut distance = unit;
+    loop {
+        let reached =
+            if distance < target.distance && !is_same_distance(&distance, &target.distance) {
+                distance
+            } else {
+                target.distance
+            };
+        let time = time_for_distance(&reached);
+        band_table.add_row(row![
+            r -> display_distance(&reached, options.use_miles),
+            r -> display_clock_time(&time)
+        ]);
+        lines.push(format!(
+            "{}  {}",
+            display_distance(&reached, options.use_miles),
+            display_clock_time(&time)
+        ));
+        if reached == target.distance {
+            break;
+        }
+        distance += unit;
+    }
+
+    report!(
+        "{}",
+        format!(
+            "Your pace band for {} in {}:",
+            display_distance(&target.distance, options.use_miles),
+            display_time(&target.time)
+        )
+        .emphasized()
+    );
+    if options.round_to.is_some() {
+        report!(
+            "Rounded to a pace of {}, you finish in {}.",
+            display_pace(&(unit / pace), options.use_miles),
+            display_time(&finish_time)
+        );
+    }
+    print_table(&band_table);
+
+    if let Some(path) = &options.out {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+        let contents = match extension.as_ref().map(|extension| &extension[..]) {
+            Some("pdf") => pace_band_pdf(&lines),
+            Some("svg") => pace_band_svg(&lines),
+            _ => {
+                return Err(anyhow!(
+                    "The file name must end in .pdf or .svg to tell the format."
+                ))
+            }
+        };
+        std::fs::write(path, contents)
+            .with_context(|| format!("Could not write the pace band to \"{}\".", path))?;
+    }
+    Ok(())
+}
+
+fn report_target(options: &TargetOptions) -> anyhow::Result<()> {
+    let target = Run::from_options(
+        &RunArguments {
+            distance: Some(options.distance.clone()),
+            time: Some(options.time.clone()),
+            pace: None,
+            fit: None,
+            gpx: None,
+            smart_units: false,
+        },
+        false,
+    )
+    .with_context(|| "Could not understand the passed arguments.")?;
+    let unit = unit_distance(options.use_miles);
+
+    report!(
+        "To run {} in {}, you need an average velocity of {}.",
+        display_distance(&target.distance, options.use_miles).emphasized(),
+        display_time(&target.time).emphasized(),
+        display_velocity(&target.average_velocity(), options.use_miles).emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "velocity = distance / time = {} / {} = {}",
+            display_distance(&target.distance, options.use_miles),
+            display_seconds(&target.time),
+            display_velocity(&target.average_velocity(), options.use_miles)
+        ));
+    }
+    report!(
+        "{}",
+        format!(
+            "That is {}.",
+            display_pace(&target.average_velocity(), options.use_miles)
+        )
+        .emphasized()
+    );
+    if options.show_formula {
+        print_formula(&format!(
+            "pace = time / distance = {} / {} = {}",
+            display_seconds(&target.time),
+            display_distance(&target.distance, options.use_miles),
+            display_time(&target.time_for_distance(&unit))
+        ));
+    }
+
+    if options.pyramid {
+        if options.pyramid_peak < 0.0 || options.pyramid_peak > 1.0 {
+            return Err(anyhow!(
+                "The peak of the pyramid must be given as a fraction between 0 and 1."
+            ));
+        }
+        let mut split_table = Table::new();
+        split_table.set_format(*format::consts::FORMAT_CLEAN);
+        let splits = target.pyramid_splits(&unit, options.pyramid_peak);
+        let velocities = splits
+            .iter()
+            .map(|split| split.average_velocity().get::<meter_per_second>())
+            .collect::<Vec<_>>();
+        let fastest = velocities.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let slowest = velocities.iter().cloned().fold(f64::INFINITY, f64::min);
+        let mut distance = Length::new::<meter>(0.0);
+        let mut time = Time::new::<second>(0.0);
+        for (split, velocity) in splits.iter().zip(velocities) {
+            distance += split.distance;
+            time += split.time;
+            let slowness = if fastest > slowest {
+                (fastest - velocity) / (fastest - slowest)
+            } else {
+                0.0
+            };
+            split_table.add_row(row![
+                r -> display_distance(&distance, options.use_miles),
+                r -> display_gradient(&display_time(&split.time), slowness),
+                r -> display_time(&time)
+            ]);
+        }
+        report!(
+            "{}",
+            "\nThese pyramid splits get you there in time:".emphasized()
+        );
+        print_table(&split_table);
+    }
+    Ok(())
+}
+
+fn report_vdot(options: &VdotOptions) -> anyhow::Result<()> {
+    let run = Run::from_options(&options.run, false)
+        .with_context(|| "Could not understand the passed arguments.")?;
+    println!("{:.1}", run.vdot());
+    Ok(())
+}
+
+fn report_pace_table(options: &PaceTableOptions) -> anyhow::Result<()> {
+    let velocity = parse_pace(&options.pace, options.use_miles)
+        .with_context(|| "Could not understand the pace.")?;
+    let mut pace_table = Table::new();
+    pace_table.set_format(*format::consts::FORMAT_CLEAN);
+    pace_table.add_row(row![r -> display_pace(&velocity, false)]);
+    pace_table.add_row(row![r -> display_pace(&velocity, true)]);
+    pace_table.add_row(row![r -> display_velocity(&velocity, false)]);
+    pace_table.add_row(row![r -> display_velocity(&velocity, true)]);
+    pace_table.add_row(row![r -> format!(
+        "{:.3} {}",
+        velocity.get::<meter_per_second>(),
+        meter_per_second::abbreviation()
+    )]);
+    print_table(&pace_table);
+    Ok(())
+}
+
+fn report_pace(options: &PaceOptions) -> anyhow::Result<()> {
+    let velocity = parse_pace(&options.pace, options.use_miles)
+        .with_context(|| "Could not understand the pace.")?;
+    // At a constant pace, any run works for the predictions, so this is the one over one unit.
+    let unit = unit_distance(options.use_miles);
+    let run = Run {
+        distance: unit,
+        time: unit / velocity,
+    };
+    report!(
+        "{}",
+        format!(
+            "At a pace of {}, your velocity is {}.",
+            display_pace(&velocity, options.use_miles),
+            display_velocity(&velocity, options.use_miles)
+        )
+        .emphasized()
+    );
+    report!(
+        "{}",
+        "\nThis is how long you would need for other distances at this pace:".emphasized()
+    );
+    print_predictions(
+        &run,
+        options.use_miles,
+        options.show_formula,
+        PredictionModel::Linear,
+        None,
+        false,
+        None,
+        None,
+    );
+    Ok(())
+}
+
+fn report_intervals(options: &IntervalOptions) -> anyhow::Result<()> {
+    if options.repeat == 0 {
+        return Err(anyhow!("There must be at least one repetition."));
+    }
+    let rep = Run::from_options(
+        &RunArguments {
+            distance: Some(options.rep_distance.clone()),
+            time: Some(options.rep_time.clone()),
+            pace: None,
+            fit: None,
+            gpx: None,
+            smart_units: false,
+        },
+        false,
+    )
+    .with_context(|| "Could not understand the repetitions.")?;
+    let rest = parse_time(&options.rest).with_context(|| "Could not understand the rest.")?;
+
+    let work = Run {
+        distance: rep.distance * options.repeat as f64,
+        time: rep.time * options.repeat as f64,
+    };
+    let total_rest = rest * (options.repeat - 1) as f64;
+    report!(
+        "In {} repetitions of {}, you ran {} in {}.",
+        options.repeat,
+        display_distance(&rep.distance, options.use_miles),
+        display_distance(&work.distance, options.use_miles).emphasized(),
+        display_time(&work.time).emphasized()
+    );
+    report!(
+        "Your average velocity in the repetitions was {} ({}).",
+        display_velocity(&rep.average_velocity(), options.use_miles).emphasized(),
+        display_pace(&rep.average_velocity(), options.use_miles)
+    );
+    report!(
+        "Including {} of rest, the session took {}.",
+        display_time(&total_rest),
+        display_time(&(work.time + total_rest)).emphasized()
+    );
+    if total_rest > Time::new::<second>(0.0) {
+        report!(
+            "Your work:rest ratio was {:.2}:1.",
+            (work.time / total_rest).get::<ratio>()
+        );
+    }
+    Ok(())
+}
+
+/// A run as read by `--json-input`.
+#[derive(Deserialize)]
+struct JsonRun {
+    distance: String,
+    time: String,
+}
+
+/// The results for a run as printed by `--json-input`.
+#[derive(Serialize)]
+struct JsonResult {
+    distance_in_meters: f64,
+    time_in_seconds: f64,
+    velocity_in_kilometers_per_hour: f64,
+    pace_in_seconds_per_kilometer: f64,
+    vdot: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warnings: Option<Vec<Warning>>,
+}
+
+fn report_json_input(options: &CommandLineOptions) -> anyhow::Result<()> {
+    // A slower pace is a lower velocity, so the minimum pace bounds the velocity from above.
+    let slowest = options
+        .max_pace
+        .as_ref()
+        .map(|pace| parse_pace(pace, false).with_context(|| "Could not understand --max-pace."))
+        .transpose()?;
+    let fastest = options
+        .min_pace
+        .as_ref()
+        .map(|pace| parse_pace(pace, false).with_context(|| "Could not understand --min-pace."))
+        .transpose()?;
+
+    let mut input = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)
+        .with_context(|| "Could not read the runs from stdin.")?;
+    let runs: Vec<JsonRun> =
+        serde_json::from_str(&input).with_context(|| "Could not understand the JSON input.")?;
+
+    let results = runs
+        .iter()
+        .enumerate()
+        .map(|(index, json_run)| {
+            let run = Run::from_options(
+                &RunArguments {
+                    distance: Some(json_run.distance.clone()),
+                    time: Some(json_run.time.clone()),
+                    pace: None,
+                    fit: None,
+                    gpx: None,
+                    smart_units: false,
+                },
+                false,
+            )
+            .with_context(|| format!("Could not understand the run at index {}.", index))?;
+            let velocity = run.average_velocity();
+            if matches!(slowest, Some(slowest) if velocity < slowest)
+                || matches!(fastest, Some(fastest) if velocity > fastest)
+            {
+                return Ok(None);
+            }
+            let warnings = implausibility(&run).into_iter().collect::<Vec<_>>();
+            let warnings = if options.sanity_json {
+                Some(warnings)
+            } else {
+                // The results on stdout stay valid JSON, so this goes to stderr.
+                for warning in &warnings {
+                    eprintln!(
+                        "{} The run at index {}: {}",
+                        "Warning:".yellow().bold(),
+                        index,
+                        warning.message
+                    );
+                }
+                None
+            };
+            Ok(Some(JsonResult {
+                distance_in_meters: run.distance.get::<meter>(),
+                time_in_seconds: run.time.get::<second>(),
+                velocity_in_kilometers_per_hour: run.average_velocity().get::<kilometer_per_hour>(),
+                pace_in_seconds_per_kilometer: (Length::new::<kilometer>(1.0)
+                    / run.average_velocity())
+                .get::<second>(),
+                vdot: run.vdot(),
+                warnings,
+            }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let total = results.len();
+    let results = results.into_iter().flatten().collect::<Vec<_>>();
+    if options.min_pace.is_some() || options.max_pace.is_some() {
+        // The results on stdout stay valid JSON, so this goes to stderr.
+        eprintln!(
+            "{} of {} runs were filtered out by their pace.",
+            total - results.len(),
+            total
+        );
+    }
+    println!("{}", serde_json::to_string_pretty(&results)?);
+    Ok(())
+}
+
+/// The global message number of the session message in a FIT file, which holds the totals.
+const FIT_SESSION_MESSAGE: u16 = 18;
+/// The field numbers within the session message.
+const FIT_TOTAL_ELAPSED_TIME_FIELD: u8 = 7;
+const FIT_TOTAL_DISTANCE_FIELD: u8 = 9;
+const FIT_AVERAGE_HEART_RATE_FIELD: u8 = 16;
+const FIT_AVERAGE_CADENCE_FIELD: u8 = 18;
+/// FIT stores the elapsed time in milliseconds and the distance in centimeters.
+const FIT_TIME_SCALE: f64 = 1000.0;
+const FIT_DISTANCE_SCALE: f64 = 100.0;
+
+/// Used to calculate the CRC-16 checksums of a FIT file, four bits at a time.
+const FIT_CRC_TABLE: [u16; 16] = [
+    0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800, 0xB401,
+    0x5000, 0x9C01, 0x8801, 0x4400,
+];
+
+/// Calculates the checksum of the bytes like the FIT SDK does.
+fn fit_crc(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        let crc = (crc >> 4)
+            ^ FIT_CRC_TABLE[usize::from(crc & 0x0F)]
+            ^ FIT_CRC_TABLE[usize::from(byte & 0x0F)];
+        (crc >> 4) ^ FIT_CRC_TABLE[usize::from(crc & 0x0F)] ^ FIT_CRC_TABLE[usize::from(byte >> 4)]
+    })
+}
+
+/// What is read from a FIT activity file.
+struct FitActivity {
+    run: Run,
+    /// The number of data messages, without the definition messages describing them.
+    records: usize,
+    average_heart_rate: Option<u8>,
+    average_cadence: Option<u8>,
+}
+
+/// The layout of the data messages of one local message type in a FIT file.
+#[derive(Clone)]
+struct FitDefinition {
+    global_message: u16,
+    big_endian: bool,
+    /// The number and size in bytes of each field.
+    fields: Vec<(u8, usize)>,
+    developer_fields_size: usize,
+}
+
+/// Reads an unsigned integer of up to four bytes, where all bits set means that the value is
+/// missing.
+fn read_fit_value(bytes: &[u8], big_endian: bool) -> Option<u32> {
+    if bytes.is_empty() || bytes.len() > 4 || bytes.iter().all(|byte| *byte == 0xFF) {
+        return None;
+    }
+    let fold = |value: u32, byte: &u8| value << 8 | u32::from(*byte);
+    Some(if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    })
+}
+
+/// Reads the totals of an activity from a FIT file, as exported by many sports watches.
+/// Only the session message is evaluated, all other messages are skipped.
+fn read_fit_activity(path: &str) -> anyhow::Result<FitActivity> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Could not read the FIT file \"{}\".", path))?;
+    let unsupported = || anyhow!("\"{}\" is not a supported FIT file.", path);
+
+    let header_size = usize::from(*bytes.first().ok_or_else(unsupported)?);
+    if header_size < 12 || bytes.len() < header_size || &bytes[8..12] != b".FIT" {
+        return Err(unsupported());
+    }
+    let data_size = read_fit_value(&bytes[4..8], false).unwrap_or(0) as usize;
+    let data = bytes
+        .get(header_size..header_size + data_size)
+        .ok_or_else(unsupported)?;
+    let checksum = bytes
+        .get(header_size + data_size..header_size + data_size + 2)
+        .ok_or_else(unsupported)?;
+    // The header checksum is optional, it is zero if it was not calculated.
+    let header_checksum = if header_size >= 14 {
+        u16::from_le_bytes([bytes[12], bytes[13]])
+    } else {
+        0
+    };
+    if (header_checksum != 0 && header_checksum != fit_crc(&bytes[..12]))
+        || u16::from_le_bytes([checksum[0], checksum[1]])
+            != fit_crc(&bytes[..header_size + data_size])
+    {
+        return Err(anyhow!(
+            "\"{}\" is damaged, its checksum does not match.",
+            path
+        ));
+    }
+
+    let mut definitions: Vec<Option<FitDefinition>> = vec![None; 16];
+    let mut records = 0;
+    let mut distance = None;
+    let mut elapsed_time = None;
+    let mut average_heart_rate = None;
+    let mut average_cadence = None;
+    let mut position = 0;
+    while position < data.len() {
+        let header = data[position];
+        position += 1;
+        // A compressed timestamp header always starts a data message.
+        let (is_definition, has_developer_fields, local_message) = if header & 0x80 != 0 {
+            (false, false, usize::from(header >> 5 & 0x03))
+        } else {
+            (
+                header & 0x40 != 0,
+                header & 0x20 != 0,
+                usize::from(header & 0x0F),
+            )
+        };
+
+        if is_definition {
+            let fixed = data.get(position..position + 5).ok_or_else(unsupported)?;
+            let big_endian = fixed[1] == 1;
+            let global_message = read_fit_value(&fixed[2..4], big_endian).unwrap_or(0) as u16;
+            let field_count = usize::from(fixed[4]);
+            position += 5;
+            let field_bytes = data
+                .get(position..position + 3 * field_count)
+                .ok_or_else(unsupported)?;
+            let fields = field_bytes
+                .chunks(3)
+                .map(|field| (field[0], usize::from(field[1])))
+                .collect();
+            position += 3 * field_count;
+            let mut developer_fields_size = 0;
+            if has_developer_fields {
+                let developer_field_count =
+                    usize::from(*data.get(position).ok_or_else(unsupported)?);
+                position += 1;
+                let developer_field_bytes = data
+                    .get(position..position + 3 * developer_field_count)
+                    .ok_or_else(unsupported)?;
+                developer_fields_size = developer_field_bytes
+                    .chunks(3)
+                    .map(|field| usize::from(field[1]))
+                    .sum();
+                position += 3 * developer_field_count;
+            }
+            definitions[local_message] = Some(FitDefinition {
+                global_message,
+                big_endian,
+                fields,
+                developer_fields_size,
+            });
+        } else {
+            let definition = definitions[local_message]
+                .as_ref()
+                .ok_or_else(unsupported)?;
+            records += 1;
+            for (number, size) in &definition.fields {
+                let value = data
+                    .get(position..position + size)
+                    .ok_or_else(unsupported)?;
+                position += size;
+                if definition.global_message != FIT_SESSION_MESSAGE {
+                    continue;
+                }
+                let value = read_fit_value(value, definition.big_endian);
+                match *number {
+                    FIT_TOTAL_ELAPSED_TIME_FIELD => elapsed_time = value.or(elapsed_time),
+                    FIT_TOTAL_DISTANCE_FIELD => distance = value.or(distance),
+                    FIT_AVERAGE_HEART_RATE_FIELD => {
+                        average_heart_rate = value.map(|value| value as u8).or(average_heart_rate)
+                    }
+                    FIT_AVERAGE_CADENCE_FIELD => {
+                        average_cadence = value.map(|value| value as u8).or(average_cadence)
+                    }
+                    _ => {}
+                }
+            }
+            position += definition.developer_fields_size;
+        }
+    }
+
+    match (distance, elapsed_time) {
+        (Some(distance), Some(elapsed_time)) => Ok(FitActivity {
+            run: Run {
+                distance: Length::new::<meter>(f64::from(distance) / FIT_DISTANCE_SCALE),
+                time: Time::new::<second>(f64::from(elapsed_time) / FIT_TIME_SCALE),
+            }
+            .validated()
+            .with_context(|| format!("The activity in \"{}\" is not a run.", path))?,
+            records,
+            average_heart_rate,
+            average_cadence,
+        }),
+        _ => Err(anyhow!(
+            "\"{}\" does not contain the total distance and time of an activity.",
+            path
+        )),
+    }
+}
+
+/// An activity read from a FIT or GPX file, together with the path of the file.
+enum Activity<'a> {
+    Fit(&'a str, FitActivity),
+    Gpx(&'a str, GpxActivity),
+}
+
+impl Activity<'_> {
+    fn run(&self) -> Run {
+        let run = match self {
+            Activity::Fit(_, activity) => &activity.run,
+            Activity::Gpx(_, activity) => &activity.run,
+        };
+        Run {
+            distance: run.distance,
+            time: run.time,
+        }
+    }
+
+    fn average_heart_rate(&self) -> Option<u32> {
+        match self {
+            Activity::Fit(_, activity) => activity.average_heart_rate.map(u32::from),
+            Activity::Gpx(..) => None,
+        }
+    }
+}
+
+/// Reads the activity file that is given instead of the distance and time, if any.
+fn read_activity(options: &RunArguments) -> anyhow::Result<Option<Activity<'_>>> {
+    if let Some(path) = &options.fit {
+        return Ok(Some(Activity::Fit(path, read_fit_activity(path)?)));
+    }
+    if let Some(path) = &options.gpx {
+        return Ok(Some(Activity::Gpx(path, read_gpx_activity(path)?)));
+    }
+    Ok(None)
+}
+
+/// The mean radius of the earth in meters, for distances between track points.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// A point of a GPX track.
+struct GpxPoint {
+    latitude: f64,
+    longitude: f64,
+    elevation: Option<f64>,
+    /// The seconds since the Unix epoch.
+    time: Option<f64>,
+}
+
+/// What is read from a GPX file.
+struct GpxActivity {
+    run: Run,
+    points: usize,
+    /// The time it would have taken on flat ground, if all points have an elevation.
+    grade_adjusted_time: Option<Time>,
+}
+
+/// The distance between two track points along the surface of the earth, by the haversine
+/// formula.
+fn gpx_distance(from: &GpxPoint, to: &GpxPoint) -> Length {
+    let (from_latitude, to_latitude) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let latitude_change = to_latitude - from_latitude;
+    let longitude_change = (to.longitude - from.longitude).to_radians();
+    let haversine = (latitude_change / 2.0).sin().powi(2)
+        + from_latitude.cos() * to_latitude.cos() * (longitude_change / 2.0).sin().powi(2);
+    Length::new::<meter>(2.0 * EARTH_RADIUS * haversine.sqrt().asin())
+}
+
+/// Parses a time like "2020-05-17T08:12:03Z" into seconds since the Unix epoch.
+fn parse_gpx_time(input: &str) -> Option<f64> {
+    let caps = GPX_TIME_REGEX.captures(input.trim())?;
+    let number = |name: &str| caps[name].parse::<i64>().ok();
+    let date = days_from_civil(number("year")?, number("month")?, number("day")?);
+    let mut seconds = (date * 24 + number("hour")?) as f64 * 3600.0
+        + number("minute")? as f64 * 60.0
+        + caps["second"].parse::<f64>().ok()?;
+    if let Some(offset) = caps.name("offset_hours") {
+        let sign = if &caps["sign"] == "-" { -1.0 } else { 1.0 };
+        let offset_minutes = caps["offset_minutes"].parse::<f64>().ok()?;
+        seconds -= sign * (offset.as_str().parse::<f64>().ok()? * 3600.0 + offset_minutes * 60.0);
+    }
+    Some(seconds)
+}
+
+/// Reads the track points of a GPX file, with their distance, elapsed time and, if every point
+/// has an elevation, the time adjusted for the grade of each segment between two points.
+fn read_gpx_activity(path: &str) -> anyhow::Result<GpxActivity> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read the GPX file \"{}\".", path))?;
+    let points = GPX_POINT_REGEX
+        .captures_iter(&content)
+        .map(|caps| {
+            let attribute = |name: &str| {
+                GPX_ATTRIBUTE_REGEX
+                    .captures_iter(&caps["attributes"])
+                    .find(|attribute| &attribute["name"] == name)
+                    .and_then(|attribute| attribute["value"].trim().parse::<f64>().ok())
+            };
+            let body = caps.name("body").map_or("", |body| body.as_str());
+            let element = |name: &str| {
+                GPX_ELEMENT_REGEX
+                    .captures_iter(body)
+                    .find(|element| &element["name"] == name)
+                    .map(|element| element["value"].to_string())
+            };
+            Some(GpxPoint {
+                latitude: attribute("lat")?,
+                longitude: attribute("lon")?,
+                elevation: element("ele").and_then(|ele| ele.trim().parse().ok()),
+                time: element("time").and_then(|time| parse_gpx_time(&time)),
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("\"{}\" contains a track point without position.", path))?;
+
+    let times = points
+        .iter()
+        .filter_map(|point| point.time)
+        .collect::<Vec<_>>();
+    if times.len() < 2 {
+        return Err(anyhow!(
+            "\"{}\" does not contain at least two track points with a time.",
+            path
+        ));
+    }
+    let run = Run {
+        distance: points
+            .windows(2)
+            .map(|pair| gpx_distance(&pair[0], &pair[1]))
+            .fold(Length::new::<meter>(0.0), |sum, distance| sum + distance),
+        time: Time::new::<second>(times[times.len() - 1] - times[0]),
+    }
+    .validated()
+    .with_context(|| format!("The track in \"{}\" is not a run.", path))?;
+
+    let grade_adjusted_time = points
+        .windows(2)
+        .map(|pair| {
+            let segment = Run {
+                distance: gpx_distance(&pair[0], &pair[1]),
+                time: Time::new::<second>(pair[1].time? - pair[0].time?),
+            };
+            let elevation = Length::new::<meter>(pair[1].elevation? - pair[0].elevation?);
+            if segment.distance > Length::new::<meter>(0.0) {
+                Some(segment.equivalent_flat_time(&elevation))
+            } else {
+                Some(segment.time)
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(|times| {
+            times
+                .into_iter()
+                .fold(Time::new::<second>(0.0), |sum, time| sum + time)
+        });
+
+    Ok(GpxActivity {
+        run,
+        points: points.len(),
+        grade_adjusted_time,
+    })
+}
+
+/// A run saved in the history, together with when it was saved.
+struct HistoryEntry {
+    /// The seconds since the Unix epoch.
+    timestamp: u64,
+    run: Run,
+    note: Option<String>,
+    tags: Vec<String>,
+}
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+fn current_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// The file given with `--history`, or `.tir_history.csv` in the home directory.
+fn history_path(options: &HistoryOptions) -> anyhow::Result<std::path::PathBuf> {
+    match &options.history {
+        Some(path) => Ok(std::path::PathBuf::from(path)),
+        None => std::env::var_os("HOME")
+            .map(|home| std::path::Path::new(&home).join(".tir_history.csv"))
+            .with_context(|| "Could not find your home directory, please use --history."),
+    }
+}
+
+/// Splits tags given like "long, trail" into their names.
+fn parse_tags(input: &str) -> Vec<String> {
+    input
+        .split(&[',', ';'][..])
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Parses a saved run of the form "<timestamp>,<meters>,<seconds>", optionally followed by
+/// ",<tags separated by semicolons>,<note>". The note comes last, so it may contain commas.
+fn parse_history_line(line: &str) -> anyhow::Result<HistoryEntry> {
+    let fields = line.split(',').collect::<Vec<_>>();
+    let parse_field = |index: usize| -> anyhow::Result<f64> {
+        fields
+            .get(index)
+            .with_context(|| "A value is missing.")?
+            .trim()
+            .parse()
+            .with_context(|| "A value is not a number.")
+    };
+    Ok(HistoryEntry {
+        timestamp: parse_field(0)? as u64,
+        run: Run {
+            distance: Length::new::<meter>(parse_field(1)?),
+            time: Time::new::<second>(parse_field(2)?),
+        },
+        note: if fields.len() > 4 {
+            Some(fields[4..].join(",")).filter(|note| !note.trim().is_empty())
+        } else {
+            None
+        },
+        tags: fields
+            .get(3)
+            .map(|tags| parse_tags(tags))
+            .unwrap_or_default(),
+    })
+}
+
+/// Reads all saved runs, a history that does not exist yet is empty.
+fn read_history(path: &std::path::Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("Could not read the history \"{}\".", path.display()))
+        }
+    };
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            parse_history_line(line).with_context(|| {
+                format!(
+                    "Line {} of the history \"{}\" is broken.",
+                    number + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Reads the saved runs, only those with the given tag if there is one.
+fn read_tagged_history(
+    options: &HistoryOptions,
+    tag: Option<&str>,
+) -> anyhow::Result<Vec<HistoryEntry>> {
+    let mut history = read_history(&history_path(options)?)?;
+    if let Some(tag) = tag {
+        history.retain(|entry| entry.tags.iter().any(|entry_tag| entry_tag == tag));
+    }
+    Ok(history)
+}
+
+fn append_history(path: &std::path::Path, entry: &HistoryEntry) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    write!(
+        file,
+        "{},{},{}",
+        entry.timestamp,
+        entry.run.distance.get::<meter>(),
+        entry.run.time.get::<second>()
+    )?;
+    // Runs without tags and note keep the short form that older versions can read.
+    if !entry.tags.is_empty() || entry.note.is_some() {
+        write!(
+            file,
+            ",{},{}",
+            entry.tags.join(";"),
+            entry.note.as_deref().unwrap_or("").replace('\n', " ")
+        )?;
+    }
+    writeln!(file)?;
+    Ok(())
+}
+
+/// Saved runs count towards the record for a standard distance if their distance deviates
+/// from it by at most this fraction.
+const PERSONAL_RECORD_DISTANCE_TOLERANCE: f64 = 0.02;
+
+fn display_days_ago(timestamp: u64) -> String {
+    match current_timestamp().saturating_sub(timestamp) / SECONDS_PER_DAY {
+        0 => String::from("today"),
+        1 => String::from("1 day ago"),
+        days => format!("{} days ago", days),
+    }
+}
+
+/// The date (in days since 1970-01-01) the week containing the given date starts at, on a
+/// Monday.
+fn week_start(date: u64) -> u64 {
+    // 1970-01-01 was a Thursday, three days after the start of its week.
+    ((date + 3) / 7 * 7).saturating_sub(3)
+}
+
+/// The year, month and day of a date given in days since 1970-01-01, following Howard
+/// Hinnant's algorithm for the proleptic Gregorian calendar.
+fn civil_date(date: u64) -> (u64, u64, u64) {
+    let shifted = date + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day_of_month)
+}
+
+/// The days since 1970-01-01 of the given date, the inverse of `civil_date`.
+fn days_from_civil(year: i64, month: i64, day_of_month: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day_of_month - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn display_date(date: u64) -> String {
+    let (year, month, day_of_month) = civil_date(date);
+    format!("{}-{:02}-{:02}", year, month, day_of_month)
+}
+
+/// Prints the number of runs and the total distance and time of the history for each period,
+/// including the periods without any runs.
+fn print_training_log(
+    options: &HistoryOptions,
+    tag: Option<&str>,
+    period: Period,
+    use_miles: bool,
+) -> anyhow::Result<()> {
+    let history = read_tagged_history(options, tag)?;
+    // Each period is identified by the date it starts at, or by the number of the month.
+    let period_of = |timestamp: u64| {
+        let date = timestamp / SECONDS_PER_DAY;
+        match period {
+            Period::Day => date,
+            Period::Week => week_start(date),
+            Period::Month => {
+                let (year, month, _) = civil_date(date);
+                year * 12 + month - 1
+            }
+        }
+    };
+    let (first, last) = match (
+        history.iter().map(|entry| period_of(entry.timestamp)).min(),
+        history.iter().map(|entry| period_of(entry.timestamp)).max(),
+    ) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            report!("Your history does not contain any runs yet.");
+            return Ok(());
+        }
+    };
+
+    report!(
+        "{}",
+        format!(
+            "Your runs per {}:",
+            match period {
+                Period::Day => "day",
+                Period::Week => "week",
+                Period::Month => "month",
+            }
+        )
+        .emphasized()
+    );
+    let mut log_table = Table::new();
+    log_table.set_format(*format::consts::FORMAT_CLEAN);
+    let mut current = first;
+    while current <= last {
+        let label = match period {
+            Period::Day => display_date(current),
+            Period::Week => format!("week of {}", display_date(current)),
+            Period::Month => format!("{}-{:02}", current / 12, current % 12 + 1),
+        };
+        let runs = history
+            .iter()
+            .filter(|entry| period_of(entry.timestamp) == current)
+            .collect::<Vec<_>>();
+        if runs.is_empty() {
+            log_table.add_row(row![label, r -> "no runs", "", "", ""]);
+        } else {
+            let total = Run {
+                distance: runs.iter().map(|entry| entry.run.distance).sum(),
+                time: runs.iter().map(|entry| entry.run.time).sum(),
+            };
+            log_table.add_row(row![
+                label,
+                r -> match runs.len() {
+                    1 => String::from("1 run"),
+                    count => format!("{} runs", count),
+                },
+                r -> display_distance(&total.distance, use_miles),
+                r -> display_time(&total.time),
+                display_pace(&total.average_velocity(), use_miles)
+            ]);
+        }
+        current += match period {
+            Period::Week => 7,
+            Period::Day | Period::Month => 1,
+        };
+    }
+    print_table(&log_table);
+    Ok(())
+}
+
+/// Prints the fastest saved run for each standard distance and how long ago it was set. Runs
+/// that were a little longer or shorter are scaled to the standard distance.
+fn print_personal_records(
+    options: &HistoryOptions,
+    tag: Option<&str>,
+    use_miles: bool,
+) -> anyhow::Result<()> {
+    let history = read_tagged_history(options, tag)?;
+
+    report!("{}", "Your personal records:".emphasized());
+    let mut record_table = Table::new();
+    record_table.set_format(*format::consts::FORMAT_CLEAN);
+    for distance in &standard_distances(use_miles) {
+        let record = history
+            .iter()
+            .filter(|entry| {
+                ((entry.run.distance / distance.distance).get::<ratio>() - 1.0).abs()
+                    <= PERSONAL_RECORD_DISTANCE_TOLERANCE
+            })
+            .map(|entry| (entry, entry.run.time_for_distance(&distance.distance)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        match record {
+            Some((record, time)) => record_table.add_row(row![
+                r -> distance.name,
+                display_time(&time),
+                format!("set {}", display_days_ago(record.timestamp))
+            ]),
+            None => record_table.add_row(row![r -> distance.name, "no record yet", ""]),
+        };
+    }
+    print_table(&record_table);
+    Ok(())
+}
+
+/// The characters of a sparkline, from the lowest to the highest value.
+const SPARKLINE_CHARACTERS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Prints a sparkline of the velocities of the runs over about the given distance in the
+/// history, in the order they were run.
+fn print_trend(
+    options: &HistoryOptions,
+    tag: Option<&str>,
+    distance: &Length,
+    use_miles: bool,
+) -> anyhow::Result<()> {
+    let mut history = read_tagged_history(options, tag)?;
+    history.retain(|entry| {
+        ((entry.run.distance / *distance).get::<ratio>() - 1.0).abs()
+            <= PERSONAL_RECORD_DISTANCE_TOLERANCE
+    });
+    history.sort_by_key(|entry| entry.timestamp);
+
+    if history.len() < 2 {
+        report!(
+            "Your history contains {} over {}, but a trend needs at least two.",
+            match history.len() {
+                0 => String::from("no runs"),
+                _ => String::from("only one run"),
+            },
+            display_distance(distance, use_miles)
+        );
+        return Ok(());
+    }
+
+    let velocities = history
+        .iter()
+        .map(|entry| entry.run.average_velocity().get::<meter_per_second>())
+        .collect::<Vec<_>>();
+    let slowest = velocities.iter().cloned().fold(f64::INFINITY, f64::min);
+    let fastest = velocities.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let levels = SPARKLINE_CHARACTERS.len() - 1;
+    let sparkline = velocities
+        .iter()
+        .map(|velocity| {
+            let level = if fastest > slowest {
+                ((velocity - slowest) / (fastest - slowest) * levels as f64).round() as usize
+            } else {
+                levels / 2
+            };
+            SPARKLINE_CHARACTERS[level.min(levels)]
+        })
+        .collect::<String>();
 
-        let time_reg = Regex::new(
-            r"((?P<hours>.+)\s*h)?\s*((?P<minutes>.+)\s*min)?((?P<seconds>.+)\s*(s|sec))?",
+    let first = history.first().expect("history is empty!");
+    let last = history.last().expect("history is empty!");
+    report!(
+        "Your pace in your {} runs over {} (higher is faster):",
+        history.len(),
+        display_distance(distance, use_miles).emphasized()
+    );
+    report!(
+        "{}",
+        display_judgement(
+            format!(
+                "{}  from {} to {}",
+                sparkline,
+                display_pace(&first.run.average_velocity(), use_miles),
+                display_pace(&last.run.average_velocity(), use_miles)
+            ),
+            last.run.average_velocity() >= first.run.average_velocity()
         )
-        .expect("time parsing regex is wrong!");
-        let time_caps = time_reg
-            .captures(&options.time)
-            .with_context(|| "Could not parse time.")?;
+    );
+    Ok(())
+}
 
-        if !["hours", "minutes", "seconds"]
-            .iter()
-            .map(|g| time_caps.name(g))
-            .any(|m| m.is_some())
+/// Parses a date like "2024-03-01" into the days since the Unix epoch.
+fn parse_date(input: &str) -> anyhow::Result<i64> {
+    let parts = input
+        .trim()
+        .split('-')
+        .map(|part| part.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| "A date must be given as year-month-day, e.g. 2024-03-01.")?;
+    match parts[..] {
+        [year, month, day_of_month]
+            if (1..=12).contains(&month) && (1..=31).contains(&day_of_month) =>
         {
-            return Err(anyhow!("No hours, no minutes, and no seconds given."));
+            Ok(days_from_civil(year, month, day_of_month))
         }
+        _ => Err(anyhow!(
+            "A date must be given as year-month-day, e.g. 2024-03-01."
+        )),
+    }
+}
 
-        let group_to_value = |group| {
-            time_caps.name(group).map_or(Ok(0.0), |m| {
-                m.as_str()
-                    .parse()
-                    .with_context(|| format!("\"{}\" is not a number", m.as_str()))
+/// Fits the times over the distance to the date by linear regression and tells when the trend
+/// would reach the target time.
+fn report_project(options: &ProjectOptions) -> anyhow::Result<()> {
+    let distance =
+        parse_distance(&options.distance).with_context(|| "Could not understand the distance.")?;
+    let target = parse_time(&options.target_time)
+        .with_context(|| "Could not understand the target time.")?;
+    // Each point is the day of a result and its time in seconds, scaled to the exact distance.
+    let mut points = if options.result.is_empty() {
+        let mut history = read_history(&history_path(&options.history)?)?;
+        history.retain(|entry| {
+            ((entry.run.distance / distance).get::<ratio>() - 1.0).abs()
+                <= PERSONAL_RECORD_DISTANCE_TOLERANCE
+        });
+        history
+            .iter()
+            .map(|entry| {
+                (
+                    entry.timestamp as f64 / SECONDS_PER_DAY as f64,
+                    entry.run.time_for_distance(&distance).get::<second>(),
+                )
             })
-        };
-        let hours =
-            group_to_value("hours").with_context(|| "Could not parse hours value as number.")?;
-        let minutes = group_to_value("minutes")
-            .with_context(|| "Could not parse minutes value as number.")?;
-        let seconds = group_to_value("seconds")
-            .with_context(|| "Could not parse seconds value as number.")?;
+            .collect::<Vec<_>>()
+    } else {
+        options
+            .result
+            .chunks(2)
+            .map(|pair| {
+                let date = parse_date(&pair[0])
+                    .with_context(|| format!("Could not understand the date \"{}\".", pair[0]))?;
+                let time = parse_time(&pair[1])
+                    .with_context(|| format!("Could not understand the time \"{}\".", pair[1]))?;
+                Ok((date as f64, time.get::<second>()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let first_day = points.first().map(|point| point.0);
+    let last_day = points.last().map(|point| point.0);
+    if points.len() < 2 || first_day == last_day {
+        report!(
+            "A projection needs at least two results over {} on different days, but there {}.",
+            display_distance(&distance, options.use_miles),
+            match points.len() {
+                0 => String::from("are none"),
+                1 => String::from("is only one"),
+                count => format!("are {} on the same day", count),
+            }
+        );
+        return Ok(());
+    }
 
-        let time =
-            Time::new::<hour>(hours) + Time::new::<minute>(minutes) + Time::new::<second>(seconds);
+    let count = points.len() as f64;
+    let mean_day = points.iter().map(|point| point.0).sum::<f64>() / count;
+    let mean_time = points.iter().map(|point| point.1).sum::<f64>() / count;
+    let slope = points
+        .iter()
+        .map(|point| (point.0 - mean_day) * (point.1 - mean_time))
+        .sum::<f64>()
+        / points
+            .iter()
+            .map(|point| (point.0 - mean_day).powi(2))
+            .sum::<f64>();
+    let latest = points.last().expect("there are no results!");
+    let current = mean_time + slope * (latest.0 - mean_day);
 
-        return Ok(Run { distance, time });
+    report!(
+        "In your {} results over {}, you got {} per week.",
+        points.len(),
+        display_distance(&distance, options.use_miles),
+        if slope < 0.0 {
+            display_judgement(
+                format!(
+                    "{} faster",
+                    display_time(&Time::new::<second>(-slope * 7.0))
+                ),
+                true,
+            )
+        } else {
+            display_judgement(
+                format!("{} slower", display_time(&Time::new::<second>(slope * 7.0))),
+                false,
+            )
+        }
+    );
+    if Time::new::<second>(current) <= target {
+        report!(
+            "Following the trend, you can already run {} in {}.",
+            display_distance(&distance, options.use_miles),
+            display_time(&target).emphasized()
+        );
+    } else if slope >= 0.0 {
+        report!(
+            "As you are not getting faster, the trend will not reach {}.",
+            display_time(&target)
+        );
+    } else {
+        let target_day = latest.0 + (target.get::<second>() - current) / slope;
+        report!(
+            "If you keep improving at this rate, you would run {} in {} around {}, in {} days.",
+            display_distance(&distance, options.use_miles),
+            display_time(&target),
+            display_date(target_day.round().max(0.0) as u64).emphasized(),
+            (target_day - latest.0).ceil()
+        );
     }
+    report!(
+        "{}",
+        "This is a naive linear extrapolation, real improvement usually slows down over time."
+            .dimmed()
+    );
+    Ok(())
+}
 
-    fn average_velocity(&self) -> Velocity {
-        return self.distance / self.time;
+/// Splits a run given as one argument of the form "<distance>@<time>", like "5km@25min".
+fn split_combined_run(arg: String) -> Vec<String> {
+    if let Some(separator) = arg.find('@') {
+        let (distance, time) = (&arg[..separator], &arg[separator + 1..]);
+        if parse_distance(distance).is_ok() && parse_time(time).is_ok() {
+            return vec![distance.to_string(), time.to_string()];
+        }
     }
+    vec![arg]
+}
 
-    fn time_for_distance(&self, other_distance: &Length) -> Time {
-        *other_distance / self.distance * self.time
+/// The options before the subcommand that take a value, which is not a subcommand then.
+const GLOBAL_OPTIONS_WITH_VALUE: [&str; 3] = ["--min-pace", "--max-pace", "--format"];
+
+/// Inserts the default subcommand `run` unless a subcommand (or a request for
+/// help or version information) is given, so that `tir 5km 25min` keeps working.
+fn command_line_arguments() -> Vec<String> {
+    let mut args: Vec<String> = std::env::args().flat_map(split_combined_run).collect();
+    let mut rest = args.iter().skip(1);
+    let first_positional = loop {
+        match rest.next() {
+            Some(arg) if GLOBAL_OPTIONS_WITH_VALUE.contains(&arg.as_str()) => {
+                rest.next();
+            }
+            Some(arg) if arg.starts_with('-') => {}
+            arg => break arg,
+        }
+    };
+    let needs_default = match first_positional {
+        None => false,
+        Some(arg) => arg != "help" && !SUBCOMMANDS.contains(&arg.as_str()),
+    };
+    if needs_default {
+        args.insert(1, String::from("run"));
     }
+    args
 }
 
-struct NamedLength {
-    name: String,
-    distance: Length,
+/// Prints the units and their spellings that are understood, taken from the same tables that
+/// are used for parsing.
+fn print_units() {
+    let join = |aliases: &[&str]| aliases.join(", ");
+    report!("{}", "Distances:".emphasized());
+    for (aliases, _) in distance_units() {
+        report!("  {}", join(aliases));
+    }
+    let mut names: Vec<String> = Vec::new();
+    for named in standard_distances(false)
+        .into_iter()
+        .chain(standard_distances(true))
+    {
+        if !names.contains(&named.name) {
+            names.push(named.name);
+        }
+    }
+    report!("  named: {}", names.join(", "));
+    report!("{}", "Times:".emphasized());
+    for (_, aliases) in &TIME_UNITS {
+        report!("  {}", join(aliases));
+    }
+    report!("  clock: [h:]mm:ss");
+    report!("{}", "Velocities:".emphasized());
+    for (aliases, _) in velocity_units() {
+        report!("  {}", join(aliases));
+    }
+    report!("{}", "Paces:".emphasized());
+    report!("  mm:ss per kilometer (or mile with --miles), or mm:ss/<distance unit>");
 }
 
-struct NamedVelocity {
-    name: String,
-    velocity: Velocity,
+/// The exit code when the command line arguments do not fit the expected usage.
+const EXIT_USAGE_ERROR: i32 = 2;
+/// The exit code when the passed values could not be understood or do not make sense.
+const EXIT_INPUT_ERROR: i32 = 3;
+/// The exit code when reading or writing failed.
+const EXIT_IO_ERROR: i32 = 4;
+
+fn exit_code(error: &anyhow::Error) -> i32 {
+    if error.chain().any(|cause| cause.is::<std::io::Error>()) {
+        EXIT_IO_ERROR
+    } else {
+        EXIT_INPUT_ERROR
+    }
 }
 
-fn display_time(time: &Time) -> String {
-    let mut t = time.clone();
+fn main() {
+    let options = match CommandLineOptions::from_iter_safe(command_line_arguments()) {
+        Ok(options) => options,
+        Err(error) if error.use_stderr() => {
+            eprintln!("{}", error.message);
+            if std::env::args().any(|arg| NEGATIVE_VALUE_REGEX.is_match(&arg)) {
+                eprintln!(
+                    "\nValues starting with \"-\" are read as flags. Distances and times have to be positive, and a negative value for an option is written like --elevation=-80m."
+                );
+            }
+            std::process::exit(EXIT_USAGE_ERROR);
+        }
+        Err(error) => error.exit(),
+    };
 
-    let hours = t.trunc::<hour>();
-    t -= hours;
-    let minutes = t.trunc::<minute>();
-    t -= minutes;
-    let seconds = t;
+    if options.no_color || options.format == ReportFormat::Markdown {
+        colored::control::set_override(false);
+    }
+    MARKDOWN.store(options.format == ReportFormat::Markdown, Ordering::Relaxed);
 
-    let h = hours.get::<hour>() as i32;
-    let m = minutes.get::<minute>() as i32;
-    let s = seconds.get::<second>();
+    if options.print_schema {
+        println!("{}", COMPARISON_SCHEMA);
+        return;
+    }
 
-    if h > 0 {
-        format!("{} h {} min {:.3} s", h, m, s)
-    } else {
-        if m > 0 {
-            format!("{} min {:.3} s", m, s)
-        } else {
-            format!("{:.3} s", s)
+    if options.list_units {
+        print_units();
+        return;
+    }
+
+    let result = match options.command {
+        _ if options.json_input => report_json_input(&options),
+        Some(Command::Run(mut options)) => {
+            // Showing results in imperial units is what --miles does.
+            options.use_miles |= options.units_out == Some(UnitSystem::Imperial);
+            report_run(&options)
         }
+        Some(Command::Predict(options)) => report_predictions(&options),
+        Some(Command::Convert(options)) => report_conversion(&options),
+        Some(Command::Summary(options)) => report_summary(&options),
+        Some(Command::Target(options)) => report_target(&options),
+        Some(Command::Intervals(options)) => report_intervals(&options),
+        Some(Command::Vdot(options)) => report_vdot(&options),
+        Some(Command::Band(options)) => report_band(&options),
+        Some(Command::Live(options)) => report_live(&options),
+        Some(Command::Plan(options)) => report_plan(&options),
+        Some(Command::PaceTable(options)) => report_pace_table(&options),
+        Some(Command::Pace(options)) => report_pace(&options),
+        Some(Command::Project(options)) => report_project(&options),
+        None => {
+            CommandLineOptions::clap()
+                .write_help(&mut std::io::stderr())
+                .expect("could not print the help!");
+            eprintln!();
+            std::process::exit(EXIT_USAGE_ERROR);
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("Error: {:?}", error);
+        std::process::exit(exit_code(&error));
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let options = CommandLineOptions::from_args();
-    let run = Run::from_options(&options)
-        .with_context(|| "Could not understand the passed arguments.")?;
-    println!(
-        "Today, you ran {} in {}.",
-        if options.use_miles {
-            format!("{:.3} {}", run.distance.get::<mile>(), mile::abbreviation()).bold()
-        } else {
-            format!(
-                "{:.3} {}",
-                run.distance.get::<kilometer>(),
-                kilometer::abbreviation()
-            )
-            .bold()
-        },
-        display_time(&run.time).bold()
-    );
-    println!(
-        "{}",
-        if options.use_miles {
-            format!(
-                "Your average velocity was {:.3} {}.",
-                run.average_velocity().get::<mile_per_hour>(),
-                mile_per_hour::abbreviation()
-            )
-            .bold()
-        } else {
-            format!(
-                "Your average velocity was {:.3} {}.",
-                run.average_velocity().get::<kilometer_per_hour>(),
-                kilometer_per_hour::abbreviation()
-            )
-            .bold()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compares quantities that are calculated with floats, up to rounding errors.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn a_comma_is_a_decimal_separator() {
+        assert_close(parse_distance("5,5km").unwrap().get::<kilometer>(), 5.5);
+        assert_close(
+            parse_distance("21,0975 km").unwrap().get::<kilometer>(),
+            21.0975,
+        );
+        assert_close(parse_time("1,5h").unwrap().get::<minute>(), 90.0);
+        assert_close(parse_time("25:30,5").unwrap().get::<second>(), 1530.5);
+        // With a dot as well, commas separate thousands.
+        assert_close(parse_distance("1,000.5 m").unwrap().get::<meter>(), 1000.5);
+    }
+
+    #[test]
+    fn only_climbing_makes_the_flat_time_faster() {
+        let run = Run {
+            distance: Length::new::<kilometer>(10.0),
+            time: Time::new::<minute>(50.0),
+        };
+        let uphill = run.equivalent_flat_time(&Length::new::<meter>(100.0));
+        let downhill = run.equivalent_flat_time(&Length::new::<meter>(-100.0));
+        assert!(uphill < run.time);
+        assert!(downhill > run.time);
+        assert_close(
+            run.equivalent_flat_time(&Length::new::<meter>(0.0))
+                .get::<second>(),
+            run.time.get::<second>(),
+        );
+        assert_close(run.grade_effort(&Length::new::<meter>(100.0)), 1.033);
+        assert_close(run.grade_effort(&Length::new::<meter>(-100.0)), 0.982);
+        // Descending more steeply than the limit does not help any further.
+        assert_close(
+            run.grade_effort(&Length::new::<meter>(-2000.0)),
+            run.grade_effort(&Length::new::<meter>(-1000.0)),
+        );
+    }
+
+    #[test]
+    fn seconds_may_have_a_fraction() {
+        assert_close(parse_time("25min30.5s").unwrap().get::<second>(), 1530.5);
+        assert_close(parse_time("25m30.5s").unwrap().get::<second>(), 1530.5);
+        assert_close(parse_time("30.25s").unwrap().get::<second>(), 30.25);
+        assert_close(parse_time("1h2min3.5s").unwrap().get::<second>(), 3723.5);
+        assert_close(parse_time("1h 0.5min").unwrap().get::<second>(), 3630.0);
+    }
+
+    #[test]
+    fn zero_and_almost_zero_times_are_displayed_cleanly() {
+        assert_eq!(display_time(&Time::new::<second>(0.0)), "0.000 s");
+        assert_eq!(display_time(&Time::new::<second>(0.0005)), "0.001 s");
+        assert_eq!(display_time(&Time::new::<second>(0.0004)), "0.000 s");
+        assert_eq!(display_time(&Time::new::<second>(-0.0004)), "0.000 s");
+        assert_eq!(display_time(&Time::new::<second>(59.9996)), "1 min 0.000 s");
+    }
+
+    #[test]
+    fn all_spellings_of_a_time_are_the_same() {
+        for input in &[
+            "1:05:00",
+            "65:00",
+            "65min",
+            "65 minutes",
+            "1h5min",
+            "1h 5m",
+            "1 hour 5 mins",
+            "1h 4min 60s",
+            "3900s",
+            "1.0833333333h",
+            "1,0833333333h",
+        ] {
+            let time = parse_time(input).unwrap_or_else(|_| panic!("\"{}\" is not a time", input));
+            assert_close(time.get::<second>(), 3900.0);
         }
-    );
+    }
 
-    if options.verbose {
-        let distances = if options.use_miles {
-            [
-                NamedLength {
-                    name: String::from("100 yd"),
-                    distance: Length::new::<yard>(100.0),
-                },
-                NamedLength {
-                    name: String::from("1/8 mi"),
-                    distance: Length::new::<mile>(0.125),
-                },
-                NamedLength {
-                    name: String::from("1/4 mi"),
-                    distance: Length::new::<mile>(0.25),
-                },
-                NamedLength {
-                    name: String::from("1 mi"),
-                    distance: Length::new::<mile>(1.0),
-                },
-                NamedLength {
-                    name: String::from("half marathon"),
-                    distance: Length::new::<kilometer>(21.0975),
-                },
-                NamedLength {
-                    name: String::from("marathon"),
-                    distance: Length::new::<kilometer>(42.195),
-                },
-            ]
-        } else {
+    #[test]
+    fn spaces_may_separate_groups_of_digits() {
+        assert_close(parse_distance("1 500 m").unwrap().get::<meter>(), 1500.0);
+        assert_close(parse_distance("10 000 m").unwrap().get::<meter>(), 10000.0);
+        assert_close(
+            parse_distance("1 000 000 m").unwrap().get::<meter>(),
+            1_000_000.0,
+        );
+        // A space before the unit still separates it from the number.
+        assert_close(parse_distance("500 m").unwrap().get::<meter>(), 500.0);
+    }
+
+    #[test]
+    fn splits_step_in_miles_with_miles() {
+        let run = Run {
+            distance: Length::new::<kilometer>(21.0975),
+            time: Time::new::<hour>(2.0),
+        };
+        let splits = run.pyramid_splits(&unit_distance(true), 0.5);
+        assert_eq!(splits.len(), 14);
+        for split in &splits[..splits.len() - 1] {
+            assert_close(split.distance.get::<mile>(), 1.0);
+        }
+        assert_close(
+            splits
+                .iter()
+                .map(|split| split.distance)
+                .sum::<Length>()
+                .get::<kilometer>(),
+            21.0975,
+        );
+
+        let names = standard_distances(true)
+            .into_iter()
+            .map(|distance| distance.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
             [
-                NamedLength {
-                    name: String::from("100 m"),
-                    distance: Length::new::<meter>(100.0),
-                },
-                NamedLength {
-                    name: String::from("1 km"),
-                    distance: Length::new::<kilometer>(1.0),
-                },
-                NamedLength {
-                    name: String::from("5 km"),
-                    distance: Length::new::<kilometer>(5.0),
-                },
-                NamedLength {
-                    name: String::from("10 km"),
-                    distance: Length::new::<kilometer>(10.0),
-                },
-                NamedLength {
-                    name: String::from("half marathon"),
-                    distance: Length::new::<kilometer>(21.0975),
-                },
-                NamedLength {
-                    name: String::from("marathon"),
-                    distance: Length::new::<kilometer>(42.195),
-                },
+                "100 yd",
+                "1/8 mi",
+                "1/4 mi",
+                "1 mi",
+                "half marathon",
+                "marathon"
             ]
+        );
+    }
+
+    #[test]
+    fn a_combined_run_is_split_at_the_at_sign() {
+        assert_eq!(
+            split_combined_run(String::from("5km@25min")),
+            ["5km", "25min"]
+        );
+        assert_eq!(
+            split_combined_run(String::from("half marathon@1:45:00")),
+            ["half marathon", "1:45:00"]
+        );
+        // Other arguments with an at sign, like an e-mail address, stay as they are.
+        assert_eq!(
+            split_combined_run(String::from("runner@example.com")),
+            ["runner@example.com"]
+        );
+        assert_eq!(split_combined_run(String::from("5km")), ["5km"]);
+    }
+
+    /// The World Athletics points of a run, which has to have a supported distance.
+    fn points(distance: Length, time: Time) -> f64 {
+        world_athletics_points(&Run { distance, time }).expect("the distance is not supported")
+    }
+
+    #[test]
+    fn world_records_score_like_in_the_scoring_tables() {
+        assert_eq!(
+            points(Length::new::<meter>(100.0), Time::new::<second>(9.58)),
+            1356.0
+        );
+        // The coefficients are approximate, so the published points are only matched roughly.
+        for (distance, time, published) in &[
+            (
+                Length::new::<kilometer>(5.0),
+                Time::new::<minute>(12.0) + Time::new::<second>(35.36),
+                1307.0,
+            ),
+            (
+                Length::new::<kilometer>(10.0),
+                Time::new::<minute>(26.0) + Time::new::<second>(11.0),
+                1310.0,
+            ),
+            (
+                Length::new::<kilometer>(21.0975),
+                Time::new::<minute>(57.0) + Time::new::<second>(31.0),
+                1324.0,
+            ),
+            (
+                Length::new::<kilometer>(42.195),
+                Time::new::<hour>(2.0) + Time::new::<minute>(1.0) + Time::new::<second>(9.0),
+                1311.0,
+            ),
+        ] {
+            let actual = points(*distance, *time);
+            assert!(
+                (actual - published).abs() <= 15.0,
+                "{} points instead of about {}",
+                actual,
+                published
+            );
+        }
+        // Slower than the time that scores no points at all.
+        assert_eq!(
+            points(Length::new::<meter>(100.0), Time::new::<second>(20.0)),
+            0.0
+        );
+    }
+
+    #[test]
+    fn unsupported_distances_have_no_points() {
+        for distance in &[
+            Length::new::<meter>(400.0),
+            Length::new::<kilometer>(15.0),
+            Length::new::<kilometer>(50.0),
+        ] {
+            let run = Run {
+                distance: *distance,
+                time: Time::new::<hour>(1.0),
+            };
+            assert_eq!(world_athletics_points(&run), None);
+        }
+    }
+
+    #[test]
+    fn time_units_have_several_spellings() {
+        assert_close(parse_time("90secs").unwrap().get::<second>(), 90.0);
+        assert_close(parse_time("90 sec").unwrap().get::<second>(), 90.0);
+        assert_close(parse_time("5mins").unwrap().get::<second>(), 300.0);
+        // In a time, "m" always means minutes and never meters.
+        assert_close(parse_time("5m").unwrap().get::<second>(), 300.0);
+        assert_close(parse_time("1h 5m 3s").unwrap().get::<second>(), 3903.0);
+    }
+
+    #[test]
+    fn unicode_fractions_are_decimals() {
+        assert_close(
+            parse_distance("½ marathon").unwrap().get::<kilometer>(),
+            21.0975,
+        );
+        assert_close(parse_distance("¼ mi").unwrap().get::<mile>(), 0.25);
+        assert_close(parse_distance("¾ km").unwrap().get::<meter>(), 750.0);
+        assert_close(parse_distance("1½ mi").unwrap().get::<mile>(), 1.5);
+        assert_close(parse_distance("⅛ mi").unwrap().get::<mile>(), 0.125);
+        assert_close(
+            parse_distance("⅓ km").unwrap().get::<kilometer>(),
+            1.0 / 3.0,
+        );
+    }
+
+    /// The arguments of a run given as distance and time.
+    fn arguments(distance: &str, time: &str) -> RunArguments {
+        RunArguments {
+            distance: Some(distance.to_string()),
+            time: Some(time.to_string()),
+            pace: None,
+            fit: None,
+            gpx: None,
+            smart_units: false,
+        }
+    }
+
+    #[test]
+    fn distance_and_time_must_be_positive() {
+        for (distance, time) in &[
+            ("0km", "25min"),
+            ("-5km", "25min"),
+            ("5km", "0s"),
+            ("5km", "-25min"),
+            ("0 m", "0:00"),
+        ] {
+            assert!(
+                Run::from_options(&arguments(distance, time), false).is_err(),
+                "{} in {} was accepted",
+                distance,
+                time
+            );
+        }
+        let run = Run::from_options(&arguments("+5km", "+25min"), false).unwrap();
+        assert_close(run.distance.get::<kilometer>(), 5.0);
+        assert_close(run.time.get::<minute>(), 25.0);
+    }
+
+    #[test]
+    fn pace_can_be_given_per_400_m() {
+        let velocity = Velocity::new::<kilometer_per_hour>(12.0);
+        let pace_unit = parse_pace_unit("400m").unwrap();
+        assert_eq!(
+            display_pace_per(&velocity, &pace_unit, PaceFormat::MinutesSeconds),
+            "2 min 0.000 s per 400 m"
+        );
+        assert_eq!(
+            display_pace_per(&velocity, &pace_unit, PaceFormat::DecimalMinutes),
+            "2.00 min per 400 m"
+        );
+        assert_eq!(
+            display_pace_per(
+                &velocity,
+                &parse_pace_unit("km").unwrap(),
+                PaceFormat::MinutesSeconds
+            ),
+            "5 min 0.000 s per km"
+        );
+        assert!(parse_pace_unit("0m").is_err());
+    }
+
+    #[test]
+    fn acsm_energy_matches_reference_values() {
+        // 10 km in 50 min are 200 m/min, so 0.2 × 200 + 3.5 = 43.5 ml/kg/min on the level.
+        let run = Run {
+            distance: Length::new::<kilometer>(10.0),
+            time: Time::new::<minute>(50.0),
         };
+        let weight = Mass::new::<kilogram>(70.0);
+        assert_close(
+            run.acsm_kilocalories(&weight, &Length::new::<meter>(0.0)),
+            43.5 / 1000.0 * 70.0 * 50.0 * 5.0,
+        );
+        // A grade of 1% adds 0.9 × 200 × 0.01 = 1.8 ml/kg/min.
+        assert_close(
+            run.acsm_kilocalories(&weight, &Length::new::<meter>(100.0)),
+            45.3 / 1000.0 * 70.0 * 50.0 * 5.0,
+        );
+        // The equation does not credit descents.
+        assert_close(
+            run.acsm_kilocalories(&weight, &Length::new::<meter>(-100.0)),
+            run.acsm_kilocalories(&weight, &Length::new::<meter>(0.0)),
+        );
+    }
 
-        let mut dist_table = Table::new();
-        dist_table.set_format(*format::consts::FORMAT_CLEAN);
-        for distance in &distances {
-            dist_table.add_row(row![
-                r -> distance.name,
-                format!(
-                    "{}",
-                    display_time(&run.time_for_distance(&distance.distance))
-                )
-            ]);
+    #[test]
+    fn an_empty_time_is_an_error() {
+        assert!(parse_time("").is_err());
+        assert!(parse_time("   ").is_err());
+        for time in &["", "  "] {
+            let error = Run::from_options(&arguments("5km", time), false).unwrap_err();
+            assert_eq!(error.to_string(), "The time must not be empty.");
+        }
+    }
+
+    #[test]
+    fn a_pace_unit_overrides_the_default() {
+        assert_close(
+            parse_pace("5:00/km", true)
+                .unwrap()
+                .get::<kilometer_per_hour>(),
+            12.0,
+        );
+        assert_close(
+            parse_pace("8:00/mi", false).unwrap().get::<mile_per_hour>(),
+            7.5,
+        );
+        assert_close(
+            parse_pace("8:00", true).unwrap().get::<mile_per_hour>(),
+            7.5,
+        );
+        assert_close(
+            parse_pace("5min/km", true)
+                .unwrap()
+                .get::<kilometer_per_hour>(),
+            12.0,
+        );
+        assert_close(
+            parse_pace("7min 15s/mi", false)
+                .unwrap()
+                .get::<mile_per_hour>(),
+            3600.0 / 435.0,
+        );
+        let error = parse_pace("5:00/lightyear", false).unwrap_err();
+        assert_eq!(error.to_string(), "Unknown pace unit \"lightyear\".");
+    }
+
+    #[test]
+    fn times_of_a_day_or_longer_show_days() {
+        assert_eq!(
+            display_time(&Time::new::<hour>(24.0)),
+            "1 d 0 h 0 min 0.000 s"
+        );
+        assert_eq!(
+            display_time(&(Time::new::<hour>(24.0) - Time::new::<second>(0.001))),
+            "23 h 59 min 59.999 s"
+        );
+        assert_eq!(
+            display_time(&(Time::new::<hour>(24.0) - Time::new::<second>(0.0004))),
+            "1 d 0 h 0 min 0.000 s"
+        );
+        assert_eq!(
+            display_time(&parse_time("1d 6h 30min 15s").unwrap()),
+            "1 d 6 h 30 min 15.000 s"
+        );
+    }
+
+    #[test]
+    fn plans_build_up_within_the_caps() {
+        let current = Length::new::<kilometer>(30.0);
+        let peak = Length::new::<kilometer>(60.0);
+        let plan = plan_weeks(current, peak, 16);
+        assert_eq!(plan.len(), 16);
+        let mut previous_build: Option<Length> = None;
+        for (week, planned) in plan.iter().enumerate() {
+            assert!(planned.distance <= peak);
+            if planned.note.is_empty() {
+                if let Some(previous) = previous_build {
+                    let cap = previous * (1.0 + MAX_WEEKLY_INCREASE);
+                    assert!(planned.distance.get::<meter>() <= cap.get::<meter>() + 1e-6);
+                }
+                previous_build = Some(planned.distance);
+            }
+            let expected = if week == 15 {
+                "race week"
+            } else if week == 14 {
+                "taper"
+            } else if week % RECOVERY_WEEK_INTERVAL == RECOVERY_WEEK_INTERVAL - 1 {
+                "recovery"
+            } else {
+                ""
+            };
+            assert_eq!(planned.note, expected, "week {}", week + 1);
+        }
+        assert_close(plan[1].distance.get::<kilometer>(), 33.0);
+        assert_close(plan[3].distance.get::<kilometer>(), 33.0 * 1.1 * 0.8);
+    }
+
+    #[test]
+    fn short_plans_taper_less() {
+        let current = Length::new::<kilometer>(30.0);
+        let peak = Length::new::<kilometer>(60.0);
+        let notes = |weeks: usize| {
+            plan_weeks(current, peak, weeks)
+                .into_iter()
+                .map(|week| week.note)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(notes(1), [""]);
+        assert_eq!(notes(2), ["", ""]);
+        assert_eq!(notes(3), ["", "", "race week"]);
+        assert_eq!(
+            notes(8),
+            ["", "", "", "recovery", "", "", "taper", "race week"]
+        );
+        assert_close(
+            plan_weeks(current, peak, 1)[0].distance.get::<kilometer>(),
+            30.0,
+        );
+    }
+
+    #[test]
+    fn miles_replace_the_kilometer_record_with_the_mile() {
+        let names = |use_miles| {
+            world_records(use_miles)
+                .into_iter()
+                .map(|record| record.name)
+                .collect::<Vec<_>>()
+        };
+        assert!(names(true).contains(&String::from("1 mi")));
+        assert!(!names(true).contains(&String::from("1 km")));
+        assert!(names(false).contains(&String::from("1 km")));
+        assert!(!names(false).contains(&String::from("1 mi")));
+        assert_eq!(names(true).len(), names(false).len());
+        // The closest record does not depend on the unit system.
+        assert_eq!(
+            closest_world_record(&Length::new::<meter>(1100.0)).name,
+            "1 km"
+        );
+    }
+
+    /// How many runs the parsing benchmark parses.
+    const BENCHMARK_RUNS: usize = 2000;
+
+    /// Compares parsing many times with the regexes compiled once to compiling them for each
+    /// time, as it was done before. Run it with
+    /// `cargo test --release -- --ignored --nocapture parsing_benchmark`.
+    #[test]
+    #[ignore]
+    fn parsing_benchmark() {
+        let times = (0..BENCHMARK_RUNS)
+            .map(|i| format!("{}min {}s", 20 + i % 40, i % 60))
+            .collect::<Vec<_>>();
+
+        let start = std::time::Instant::now();
+        for time in &times {
+            assert!(TIME_REGEX.captures(time).is_some());
         }
+        let compiled_once = start.elapsed();
+
+        let start = std::time::Instant::now();
+        for time in &times {
+            let regex = Regex::new(&time_pattern()).unwrap();
+            assert!(regex.captures(time).is_some());
+        }
+        let compiled_each_time = start.elapsed();
 
         println!(
-            "{}",
-            "\nThis is how long you would have needed for other distances:".bold()
+            "Parsing {} times took {:?} with the regex compiled once and {:?} compiling it each time ({:.0}× faster).",
+            BENCHMARK_RUNS,
+            compiled_once,
+            compiled_each_time,
+            compiled_each_time.as_secs_f64() / compiled_once.as_secs_f64()
         );
-        dist_table.printstd();
+        assert!(compiled_once < compiled_each_time);
+    }
 
-        let velocities = &[
-            NamedVelocity {
-                name: String::from("Ashprihanal Aalto\'s 3100 mi (longest ultra marathon) WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(5.1480),
-            },
-            NamedVelocity {
-                name: String::from("Yohann Diniz\' 50 km race walk WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(14.1143),
-            },
-            NamedVelocity {
-                name: String::from("Eliud Kipchoge\'s inofficial marathon WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(21.1563),
-            },
-            NamedVelocity {
-                name: String::from("Kenenisa Bekele\'s 10000 m WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(22.8205),
-            },
-            NamedVelocity {
-                name: String::from("Usain Bolt\'s 100 m WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(37.5783),
-            },
-            NamedVelocity {
-                name: String::from("Cheetah Sarah\'s 100 m animal WR"),
-                velocity: Velocity::new::<kilometer_per_hour>(60.5042),
-            },
+    /// Writes a FIT file with one session message to the temporary directory.
+    fn fit_file(name: &str, distance: u32, elapsed_time: u32) -> (String, Vec<u8>) {
+        let mut data = vec![0x40, 0, 0];
+        data.extend_from_slice(&FIT_SESSION_MESSAGE.to_le_bytes());
+        data.extend_from_slice(&[
+            2,
+            FIT_TOTAL_ELAPSED_TIME_FIELD,
+            4,
+            0x86,
+            FIT_TOTAL_DISTANCE_FIELD,
+            4,
+            0x86,
+            0,
+        ]);
+        data.extend_from_slice(&elapsed_time.to_le_bytes());
+        data.extend_from_slice(&distance.to_le_bytes());
+        let mut bytes = vec![14, 0x10, 0, 0];
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b".FIT");
+        let header_checksum = fit_crc(&bytes);
+        bytes.extend_from_slice(&header_checksum.to_le_bytes());
+        bytes.extend_from_slice(&data);
+        let checksum = fit_crc(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, &bytes).unwrap();
+        (path.to_str().unwrap().to_string(), bytes)
+    }
+
+    #[test]
+    fn fit_checksums_are_crc_16() {
+        assert_eq!(fit_crc(b"123456789"), 0xBB3D);
+    }
+
+    #[test]
+    fn fit_files_count_only_data_messages() {
+        let (path, _) = fit_file("tir-test-run.fit", 1_000_000, 3_000_000);
+        let activity = read_fit_activity(&path).unwrap();
+        assert_eq!(activity.records, 1);
+        assert_close(activity.run.distance.get::<kilometer>(), 10.0);
+        assert_close(activity.run.time.get::<minute>(), 50.0);
+    }
+
+    #[test]
+    fn damaged_fit_files_are_rejected() {
+        let (path, mut bytes) = fit_file("tir-test-damaged.fit", 1_000_000, 3_000_000);
+        let last_data_byte = bytes.len() - 3;
+        bytes[last_data_byte] ^= 0x01;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(read_fit_activity(&path).is_err());
+    }
+
+    #[test]
+    fn fit_files_without_distance_are_rejected() {
+        let (path, _) = fit_file("tir-test-empty.fit", 0, 3_000_000);
+        assert!(read_fit_activity(&path).is_err());
+    }
+
+    #[test]
+    fn pace_band_pdf_offsets_point_at_the_objects() {
+        let lines = vec![
+            String::from("Pace band (10 km)"),
+            String::from("1 km — 4:59"),
+            String::from("2 km \\ 9:58"),
         ];
+        let pdf = pace_band_pdf(&lines);
+        let startxref = pdf.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref = pdf[startxref..]
+            .lines()
+            .next()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[xref..].starts_with("xref\n0 6\n"));
+        let entries = pdf[xref..].lines().skip(3).take(5);
+        for (index, entry) in entries.enumerate() {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", index + 1)));
+        }
+        let stream = pdf.find("stream\n").unwrap() + "stream\n".len();
+        let length = pdf[pdf.find("/Length ").unwrap() + "/Length ".len()..]
+            .split(' ')
+            .next()
+            .unwrap()
+            .parse::<usize>()
+            .unwrap();
+        assert!(pdf[stream + length..].starts_with("endstream"));
+    }
 
-        let mut vel_table = Table::new();
-        vel_table.set_format(*format::consts::FORMAT_CLEAN);
-        for velocity in velocities {
-            vel_table.add_row(row![
-                r -> format!(
-                    "{:.3} times",
-                    (run.average_velocity() / velocity.velocity).get::<ratio>()
-                ),
-                velocity.name
-            ]);
+    #[test]
+    fn goal_days_must_be_positive() {
+        let goal = |goal_days| GoalOptions {
+            goal: vec!["1:40:00".to_string(), "half marathon".to_string()],
+            goal_days,
+        };
+        assert!(parse_goal(&goal(Some(0.0))).is_err());
+        assert!(parse_goal(&goal(Some(-3.0))).is_err());
+        assert!(parse_goal(&goal(Some(30.0))).unwrap().is_some());
+        assert!(parse_goal(&goal(None)).unwrap().is_some());
+    }
+
+    #[test]
+    fn rejected_runs_are_not_saved() {
+        let history = std::env::temp_dir().join("tir_rejected_runs_are_not_saved.csv");
+        let _ = std::fs::remove_file(&history);
+        let history = history.to_str().unwrap();
+        for rejected in &[
+            vec!["--warmup", "6km:30min"],
+            vec!["--elapsed", "20min"],
+            vec!["--goal", "20:00", "5km", "--goal-days", "0"],
+        ] {
+            let mut arguments = vec!["run", "5km", "25min", "--save", "--history", history];
+            arguments.extend(rejected);
+            assert!(report_run(&RunOptions::from_iter(arguments)).is_err());
+            assert!(!std::path::Path::new(history).exists());
         }
+        let accepted = vec!["run", "5km", "25min", "--save", "--history", history];
+        assert!(report_run(&RunOptions::from_iter(accepted)).is_ok());
+        assert_eq!(
+            read_history(std::path::Path::new(history)).unwrap().len(),
+            1
+        );
+        std::fs::remove_file(history).unwrap();
+    }
 
-        println!(
-            "{}",
-            "\nYour average velocity compared to those of other performances:".bold()
+    #[test]
+    fn share_lines_show_the_pace_like_a_clock() {
+        let share = |arguments: &[&str]| {
+            let options = RunOptions::from_iter(arguments);
+            let run = Run::from_options(&options.run, options.input_in_miles()).unwrap();
+            share_line(&run, &options, &RunInputs::parse(&options, &run).unwrap())
+        };
+        assert_eq!(
+            share(&["run", "5km", "26min", "--share"]),
+            "🏃 Ran 5.0 km in 26:00 — 5:12/km pace!"
+        );
+        assert_eq!(
+            share(&["run", "5km", "26min", "--share", "--no-emoji"]),
+            "Ran 5.0 km in 26:00 — 5:12/km pace!"
         );
-        vel_table.printstd();
     }
-    Ok(())
+
+    #[test]
+    fn input_file_errors_name_the_line() {
+        let path = std::env::temp_dir().join("tir_input_file_errors_name_the_line.csv");
+        std::fs::write(&path, "distance,time\n5km,25min\n\n10km,52min\nfoo,bar\n").unwrap();
+        let error = read_input_file(path.to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("line 5"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    /// Writes a GPX file with the given track points (latitude, longitude and time) to the
+    /// temporary directory.
+    fn gpx_file(name: &str, points: &[(f64, f64, Option<&str>)]) -> String {
+        let track_points = points
+            .iter()
+            .map(|(latitude, longitude, time)| {
+                format!(
+                    "<trkpt lat=\"{}\" lon=\"{}\">{}</trkpt>\n",
+                    latitude,
+                    longitude,
+                    time.map_or(String::new(), |time| format!("<time>{}</time>", time))
+                )
+            })
+            .collect::<String>();
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(
+            &path,
+            format!(
+                "<gpx><trk><trkseg>\n{}</trkseg></trk></gpx>\n",
+                track_points
+            ),
+        )
+        .unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn gpx_tracks_are_read() {
+        let path = gpx_file(
+            "tir-test-run.gpx",
+            &[
+                (48.0, 11.0, Some("2020-05-01T07:00:00Z")),
+                (48.01, 11.0, Some("2020-05-01T07:05:00Z")),
+            ],
+        );
+        let activity = read_gpx_activity(&path).unwrap();
+        assert!((activity.run.distance.get::<kilometer>() - 1.112).abs() < 0.01);
+        assert_close(activity.run.time.get::<minute>(), 5.0);
+    }
+
+    #[test]
+    fn gpx_tracks_without_times_are_rejected() {
+        let path = gpx_file(
+            "tir-test-untimed.gpx",
+            &[(48.0, 11.0, None), (48.01, 11.0, None)],
+        );
+        assert!(read_gpx_activity(&path).is_err());
+    }
+
+    #[test]
+    fn gpx_tracks_with_a_single_point_are_rejected() {
+        let path = gpx_file(
+            "tir-test-single.gpx",
+            &[(48.0, 11.0, Some("2020-05-01T07:00:00Z"))],
+        );
+        assert!(read_gpx_activity(&path).is_err());
+    }
+
+    #[test]
+    fn gpx_tracks_without_distance_are_rejected() {
+        let path = gpx_file(
+            "tir-test-standing.gpx",
+            &[
+                (48.0, 11.0, Some("2020-05-01T07:00:00Z")),
+                (48.0, 11.0, Some("2020-05-01T07:05:00Z")),
+            ],
+        );
+        assert!(read_gpx_activity(&path).is_err());
+    }
 }